@@ -1,8 +1,14 @@
+use std::time::Duration;
+
 use bevy::{prelude::*, ecs::system::EntityCommands};
 use bevy_prototype_lyon::prelude::*;
 
 use crate::*;
 
+pub const EXPLOSION_SHEET: &str = "data/fx/explo_a_sheet.png";  // Used by platforms without their own death effects
+
+const SECONDARY_EXPLOSION_SCALE: f32 = 0.5;  // Relative to the first explosion
+
 pub fn explosion_to_spawn_system(
 	mut commands: Commands,
 	query: Query<(Entity, &ExplosionToSpawn)>,
@@ -10,35 +16,133 @@ pub fn explosion_to_spawn_system(
 ) {
 	for (explosion_spawn_entity, explosion_to_spawn) in query.iter() {
 		// spawn the explosion sprite
+		let animation = texture_server.animation(&explosion_to_spawn.sheet, None).despawning();
         commands
             .spawn_bundle(SpriteSheetBundle {
-                texture_atlas: texture_server.get(&String::from("data/fx/explo_a_sheet.png")).typed::<TextureAtlas>(),
+                texture_atlas: texture_server.get_atlas(&explosion_to_spawn.sheet),
+                sprite: TextureAtlasSprite {
+                    index: animation.range.first,
+                    custom_size: Some(Vec2::splat(explosion_to_spawn.size)),
+                    ..Default::default()
+                },
                 transform: Transform {
-                    translation: explosion_to_spawn.0,
+                    translation: explosion_to_spawn.position,
                     ..Default::default()
                 },
                 ..Default::default()
             })
             .insert(Explosion)
-            .insert(ExplosionTimer::default());
+            .insert(animation);
 
         // despawn the explosionToSpawn
         commands.entity(explosion_spawn_entity).despawn();
     }
 }
 
-pub fn explosion_animation_system(
+// Explosions and debris for a unit destroyed at `body`
+pub fn spawn_death_effects(
+    commands: &mut Commands,
+    death: &DeathData,
+    body: &Body,
+    velocity: Option<&Velocity>,
+    texture_server: &Res<TextureServer>
+) {
+    let position = Vec3::new(body.position.x, body.position.y, EXPLOSION_ZORDER);
+    let size = body.size.max_element() * SPRITE_SCALE * death.scale;
+    commands.spawn().insert(ExplosionToSpawn { position, sheet: death.explosion.clone(), size });
+    if death.secondary_explosions > 0 {
+        commands.spawn().insert(DeathSequence {
+            timer: Timer::from_seconds(death.secondary_interval.max(0.01), true),
+            remaining: death.secondary_explosions,
+            position,
+            radius: body.collision_radius,
+            sheet: death.explosion.clone(),
+            size: size * SECONDARY_EXPLOSION_SCALE
+        });
+    }
+    let (dx, dy) = velocity.map(|velocity| (velocity.dx, velocity.dy)).unwrap_or((0., 0.));
+    for debris in death.debris.iter() {
+        for _ in 0..debris.count {
+            let angle = rand::random::<f32>() * 2. * PI;
+            let speed = rand::random::<f32>() * debris.speed;
+            let mut bundle = sprite_bundle_from_data(&debris.sprite, texture_server, 0.);
+            bundle.transform.translation = position + Vec3::new(angle.cos(), angle.sin(), 0.) * body.collision_radius * rand::random::<f32>()
+                - Vec3::Z;
+            bundle.transform.rotation = Quat::from_rotation_z(rand::random::<f32>() * 2. * PI);
+            commands.spawn_bundle(bundle)
+                .insert(Debris { timer: Timer::from_seconds(debris.lifetime, false) })
+                .insert(Velocity {
+                    dx: dx + angle.cos() * speed,
+                    dy: dy + angle.sin() * speed,
+                    dw: (rand::random::<f32>() - 0.5) * 2. * debris.spin
+                });
+        }
+    }
+}
+
+pub fn death_sequence_system(
+	mut commands: Commands,
+	time: Res<Time>,
+	mut query: Query<(Entity, &mut DeathSequence)>,
+) {
+	for (entity, mut sequence) in query.iter_mut() {
+		sequence.timer.tick(time.delta());
+		for _ in 0..sequence.timer.times_finished_this_tick() {
+			let angle = rand::random::<f32>() * 2. * PI;
+			let offset = Vec3::new(angle.cos(), angle.sin(), 0.) * sequence.radius * rand::random::<f32>();
+			commands.spawn().insert(ExplosionToSpawn {
+				position: sequence.position + offset,
+				sheet: sequence.sheet.clone(),
+				size: sequence.size
+			});
+			sequence.remaining = sequence.remaining.saturating_sub(1);
+			if sequence.remaining == 0 {
+				commands.entity(entity).despawn();
+				break
+			}
+		}
+	}
+}
+
+// Moves with the unit updates, so it ages by one tick per step
+pub fn debris_system(
+	mut commands: Commands,
+	mut query: Query<(Entity, &mut Debris, &Velocity, &mut Transform, &mut Sprite)>,
+) {
+	for (entity, mut debris, velocity, mut transform, mut sprite) in query.iter_mut() {
+		debris.timer.tick(Duration::from_secs_f32(UNIT_TICK));
+		if debris.timer.finished() {
+			commands.entity(entity).despawn();
+			continue
+		}
+		transform.translation.x += velocity.dx;
+		transform.translation.y += velocity.dy;
+		transform.rotate_z(velocity.dw);
+		sprite.color.set_a(debris.timer.percent_left());
+	}
+}
+
+pub fn animated_sprite_system(
 	mut commands: Commands,
 	time: Res<Time>,
-	mut query: Query<(Entity, &mut ExplosionTimer, &mut TextureAtlasSprite), With<Explosion>>,
+	mut query: Query<(Entity, &mut AnimatedSprite, &mut TextureAtlasSprite)>,
 ) {
-	for (entity, mut timer, mut sprite) in query.iter_mut() {
-        timer.0.tick(time.delta());
-		if timer.0.finished() {
-			sprite.index += 1; // move to next sprite cell
-			if sprite.index >= 16 {  // TODO manage fx sheets
-				commands.entity(entity).despawn()
+	for (entity, mut animation, mut sprite) in query.iter_mut() {
+		if animation.finished {
+			continue
+		}
+        animation.timer.tick(time.delta());
+		for _ in 0..animation.timer.times_finished_this_tick() {
+			match animation.next_frame(sprite.index) {
+				Some(index) => sprite.index = index,
+				None => {
+					animation.finished = true;
+					if animation.despawn_when_finished {
+						commands.entity(entity).despawn();
+					}
+					break
+				}
 			}
 		}
 	}
-}
\ No newline at end of file
+}
@@ -0,0 +1,80 @@
+// Converts a Rusted Warfare unit into konquer unit data.
+//
+//     konquer-import-rw UNIT_INI [PACK_DIR]
+//
+// Writes the platform, subunits, projectiles and assembly as JSON beneath PACK_DIR (default
+// `assets/mods/rusted_warfare`) and copies the unit's images alongside them, so the result loads as a mod pack.
+
+use std::{env, fs, path::{Path, PathBuf}, process};
+
+use serde::Serialize;
+
+use konquer::*;
+
+fn main() {
+    let ini = match env::args().nth(1) {
+        Some(ini) => PathBuf::from(ini),
+        None => {
+            eprintln!("Usage: konquer-import-rw UNIT_INI [PACK_DIR]");
+            process::exit(2);
+        }
+    };
+    let pack_dir = env::args().nth(2).map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(ASSET_DIR).join(MODS_DIR).join("rusted_warfare"));
+
+    let import = match import_rusted_warfare_unit(&ini) {
+        Ok(import) => import,
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+    };
+
+    write_json(&pack_dir, PLATFORM_DIR, &import.platform.name, &import.platform);
+    for subunit in import.subunits.iter() {
+        write_json(&pack_dir, SUBUNIT_DIR, &subunit.name, subunit);
+    }
+    for projectile in import.projectiles.iter() {
+        write_json(&pack_dir, PROJECTILE_DIR, &projectile.name, projectile);
+    }
+    write_json(&pack_dir, ASSEMBLY_DIR, &import.assembly.name, &import.assembly);
+    for (source, key) in import.textures.iter() {
+        let target = pack_dir.join(key);
+        create_parent(&target);
+        if let Err(error) = fs::copy(source, &target) {
+            eprintln!("{}: could not be copied to {}: {}", source.display(), target.display(), error);
+            process::exit(1);
+        }
+    }
+
+    println!(
+        "Imported '{}' with {} subunits and {} projectiles into {}",
+        import.assembly.name,
+        import.subunits.len(),
+        import.projectiles.len(),
+        pack_dir.display()
+    );
+    for warning in import.warnings.iter() {
+        eprintln!("    warning: {}", warning);
+    }
+}
+
+// Each definition gets its own directory, as in the base assets
+fn write_json<T: Serialize>(pack_dir: &Path, dir: &str, name: &str, data: &T) {
+    let path = pack_dir.join(dir).join(name).join(format!("{}.json", name));
+    create_parent(&path);
+    let s = serde_json::to_string_pretty(data).expect("Unit data should serialize");
+    if let Err(error) = fs::write(&path, s) {
+        eprintln!("{}: could not be written: {}", path.display(), error);
+        process::exit(1);
+    }
+}
+
+fn create_parent(path: &Path) {
+    if let Some(parent) = path.parent() {
+        if let Err(error) = fs::create_dir_all(parent) {
+            eprintln!("{}: could not be created: {}", parent.display(), error);
+            process::exit(1);
+        }
+    }
+}
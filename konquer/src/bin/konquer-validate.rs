@@ -0,0 +1,74 @@
+// Checks the unit data beneath an asset directory, including any mods, without starting the game.
+//
+//     konquer-validate [ASSET_DIR]
+//
+// Exits with a non-zero status if any definition fails to load or resolve.
+//
+//     konquer-validate schema [OUT_DIR]
+//
+// Writes a JSON Schema for each kind of data file to OUT_DIR (default `schemas`) for use by editors and other tools.
+
+use std::{env, fs, path::{Path, PathBuf}, process};
+
+use konquer::*;
+
+const SCHEMA_DIR: &str = "schemas";
+const REFERENCE_HP: u64 = 100;  // Maximum hp of the target that damage per second is quoted against
+
+fn main() {
+    if env::args().nth(1).as_deref() == Some("schema") {
+        let out_dir = env::args().nth(2).map(PathBuf::from).unwrap_or_else(|| PathBuf::from(SCHEMA_DIR));
+        write_schemas(&out_dir);
+        return
+    }
+    let asset_dir = env::args().nth(1).map(PathBuf::from).unwrap_or_else(|| PathBuf::from(ASSET_DIR));
+    let packs = DataPacks::discover(&asset_dir);
+    let (database, mut errors) = UnitDatabase::load_packs_partial(&packs.0);
+    check_texture_data(&packs.0, &mut errors);
+
+    for pack in packs.0.iter() {
+        println!("Data pack '{}' at {}", pack.name, pack.root.display());
+    }
+    println!(
+        "Checked {} subunits, {} platforms, {} projectiles and {} assemblies",
+        database.subunits.values().count(),
+        database.platforms.values().count(),
+        database.projectiles.values().count(),
+        database.assemblies.iter().count()
+    );
+    let mut units: Vec<&UnitData> = database.units.values().collect();
+    units.sort_by(|a, b| a.name.cmp(&b.name));
+    for unit in units {
+        let stats = &unit.stats;
+        println!(
+            "    {}: {} hp, {} shield, mass {}, acceleration {}, turn rate {}, {} dps + {:.1}% max hp/s ({:.1} dps vs {} hp), range {}, builds in {}s for {}",
+            unit.name, stats.hp, stats.shield, stats.mass, stats.acceleration, stats.turn_rate, stats.total_dps(),
+            stats.max_hp_per_second * 100., stats.dps_against(REFERENCE_HP), REFERENCE_HP, stats.max_range, unit.build_time, unit.cost
+        );
+    }
+    if errors.is_empty() {
+        println!("All unit data is valid.");
+        return
+    }
+    eprintln!("Found {} problem(s):", errors.len());
+    for error in errors.iter() {
+        eprintln!("    {}", error);
+    }
+    process::exit(1);
+}
+
+fn write_schemas(out_dir: &Path) {
+    if let Err(error) = fs::create_dir_all(out_dir) {
+        eprintln!("{}: could not be created: {}", out_dir.display(), error);
+        process::exit(1);
+    }
+    for (kind, schema) in data_schemas() {
+        let path = out_dir.join(format!("{}.schema.json", kind));
+        let s = serde_json::to_string_pretty(&schema).expect("Schemas should serialize");
+        if let Err(error) = fs::write(&path, s) {
+            eprintln!("{}: could not be written: {}", path.display(), error);
+            process::exit(1);
+        }
+        println!("Wrote {}", path.display());
+    }
+}
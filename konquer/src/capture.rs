@@ -0,0 +1,63 @@
+use bevy::prelude::*;
+
+use crate::*;
+
+const CAPTURE_TIME: f32 = 20.;  // Seconds a lone player needs to take a planet
+
+// Units inside a planet's gravity radius take it over when no other player's units are there to contest it.
+// Progress towards a new owner first has to wind down any progress another player had made.
+pub fn planet_capture_system(
+    q_units: Query<(&Unit, &Body)>,
+    mut q_planets: Query<(&EnvironmentalSatellite, &PrimarySatellite, &Transform, &mut Owner, &mut Capture)>,
+) {
+    let step = UNIT_TICK / CAPTURE_TIME;
+    for (planet, primary_sat, transform, mut owner, mut capture) in q_planets.iter_mut() {
+        let position = transform.translation.truncate();
+        let mut present: Vec<&Player> = Vec::new();
+        for (unit, body) in q_units.iter() {
+            if position.distance(body.position.truncate()) < primary_sat.gravity_radius && !present.contains(&&unit.player) {
+                present.push(&unit.player);
+            }
+        }
+        match present.as_slice() {
+            [player] if owner.player.as_ref() != Some(*player) => {
+                if capture.player.as_ref() == Some(*player) {
+                    capture.progress += step;
+                }
+                else {
+                    // Someone else's progress has to be undone first
+                    capture.progress -= step;
+                    if capture.progress <= 0. {
+                        capture.progress = 0.;
+                        capture.player = Some((*player).clone());
+                    }
+                }
+                if capture.progress >= 1. {
+                    println!("Player {} captured {}", player.id, planet.name);
+                    owner.player = Some((*player).clone());
+                    *capture = Capture::default();
+                }
+            },
+            [_, _, ..] => (),  // Contested, so nothing changes
+            _ => {
+                // Abandoned or held by its owner
+                capture.progress = (capture.progress - step).max(0.);
+                if capture.progress == 0. {
+                    capture.player = None;
+                }
+            }
+        }
+    }
+}
+
+// Moons belong to whoever holds the planet they orbit
+pub fn moon_ownership_system(
+    q_planets: Query<&Owner, (With<PrimarySatellite>, Changed<Owner>)>,
+    mut q_moons: Query<(&Orbit, &mut Owner), Without<PrimarySatellite>>,
+) {
+    for (orbit, mut owner) in q_moons.iter_mut() {
+        if let Ok(planet_owner) = q_planets.get(orbit.parent) {
+            owner.player = planet_owner.player.clone();
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use std::{collections::VecDeque, marker::PhantomData, time::Duration};
 use bevy::{prelude::{Component, Entity, Color}, math::{Vec2, Vec3}, ecs::{archetype::Archetypes, component::ComponentId}, time::{Timer, Time}};
 use std::{sync::atomic::{AtomicU8, Ordering}};
-use crate::{Player, SPRITE_SCALE};
+use crate::{Player, SPRITE_SCALE, FrameRange, LoopMode, TextureData, DamageType, SubunitData, SubunitClassData};
 
 
 pub fn get_components_for_entity<'a>(
@@ -120,6 +120,25 @@ pub struct EnvironmentalSatellite {
 #[derive(Component)]
 pub struct PlanetIllumination;
 
+// The player holding a satellite and collecting its income, if any
+#[derive(Component, Default)]
+pub struct Owner {
+    pub player: Option<Player>
+}
+
+// A player's progress towards taking a planet, from 0 to 1
+#[derive(Component, Default)]
+pub struct Capture {
+    pub player: Option<Player>,
+    pub progress: f32
+}
+
+// The owner line of a planet's info label
+#[derive(Component)]
+pub struct PlanetOwnerText {
+    pub planet: Entity
+}
+
 // The sun is the large body at the middle of the map
 #[derive(Component, Clone, Copy)]
 pub struct Sun;
@@ -200,6 +219,34 @@ pub struct UnitPathDisplay;
 #[derive(Component)]
 pub struct CapitalShip;
 
+// Depots are large, slow platforms that serve as a base of operations for other units
+#[derive(Component)]
+pub struct Depot;
+
+// Assemblies waiting to be built by a depot, in order
+#[derive(Component, Default)]
+pub struct ProductionQueue {
+    pub queue: VecDeque<String>,
+    pub timer: Option<Timer>,  // Progress on the front of the queue, started once it is being built
+    pub rally: UnitPathNodes,  // The path given to each unit as it is completed
+}
+
+impl ProductionQueue {
+    pub fn new() -> ProductionQueue {
+        ProductionQueue::default()
+    }
+    pub fn push(&mut self, assembly: String) {
+        self.queue.push_back(assembly);
+    }
+    pub fn current(&self) -> Option<&String> {
+        self.queue.front()
+    }
+    // Fraction of the current unit that has been built
+    pub fn progress(&self) -> f32 {
+        self.timer.as_ref().map_or(0., |timer| timer.percent())
+    }
+}
+
 #[derive(Component)]
 pub struct ProjectileSprite;
 
@@ -208,7 +255,29 @@ pub struct Projectile {
     pub player: Player,
     pub fired_from: Vec2,
     pub range: f32,
-    pub damage: f32
+    pub damage: f32,
+    pub damage_per_max_hp: f32,
+    pub damage_type: DamageType
+}
+
+// Steers a projectile towards `target` while it has fuel. The lock is lost for good once the target leaves the seeker cone.
+#[derive(Component)]
+pub struct Missile {
+    pub target: Option<Entity>,
+    pub fuel: Timer,
+    pub turn_rate: f32,
+    pub acceleration: f32,
+    pub seeker_cone: f32,  // Radians
+}
+
+// Projectiles that point defense can target and destroy
+#[derive(Component)]
+pub struct Interceptable;
+
+// The player who most recently damaged a unit
+#[derive(Component)]
+pub struct LastAttacker {
+    pub player: Player
 }
 
 
@@ -221,6 +290,7 @@ pub struct Turret {
     pub timer: Timer,
     pub firing_pattern: String,
     pub sources: Vec<Vec2>,
+    pub fixed_arc: Option<f32>,  // Radians either side of the nose for weapons that cannot rotate
     source_index: usize
 }
 
@@ -234,9 +304,38 @@ impl Turret {
             timer: Timer::new(Duration::from_millis(reload_time), false),
             firing_pattern,
             sources,
+            fixed_arc: None,
             source_index: 0
         }
     }
+    // Locks the turret to the hull, firing only at targets within `firing_arc` of the nose
+    pub fn fixed(mut self, firing_arc: f32) -> Self {
+        self.fixed_arc = Some(firing_arc);
+        self
+    }
+    // The turret a weapon subunit fires with, or None for subunits that do not fire
+    pub fn from_subunit_data(subunit_data: &SubunitData) -> Option<Self> {
+        let vec_sources = |sources: &Vec<Vec<f32>>| sources.iter().map(|source| Vec2::new(source[0], source[1])).collect();
+        match &subunit_data.class {
+            SubunitClassData::Turret { reload_time, fire_range, projectile, firing_pattern, sources, .. } => Some(Turret::new(
+                subunit_data.name.clone(),
+                projectile.clone(),
+                *fire_range,
+                *reload_time,
+                firing_pattern.clone(),
+                vec_sources(sources)
+            )),
+            SubunitClassData::ForwardWeapon { reload_time, fire_range, firing_arc, projectile, sources } => Some(Turret::new(
+                subunit_data.name.clone(),
+                projectile.clone(),
+                *fire_range,
+                *reload_time,
+                "simultaneous".to_string(),
+                vec_sources(sources)
+            ).fixed(firing_arc.to_radians())),
+            _ => None
+        }
+    }
     pub fn get_sources(&self) -> Vec<Vec2> {
         match self.firing_pattern.as_str() {
             "alternating" => {
@@ -257,6 +356,16 @@ impl Turret {
         self.timer = Timer::new(Duration::from_millis(self.reload_time), false);
         self.source_index = (self.source_index + 1).rem_euclid(self.sources.len());
     }
+    // Replaces the turret's stats without interrupting the current reload
+    pub fn reconfigure(&mut self, turret: Turret) {
+        self.projectile = turret.projectile;
+        self.range = turret.range;
+        self.reload_time = turret.reload_time;
+        self.firing_pattern = turret.firing_pattern;
+        self.sources = turret.sources;
+        self.fixed_arc = turret.fixed_arc;
+        self.source_index = 0;
+    }
 
 }
 
@@ -311,12 +420,48 @@ pub struct HealthBar;
 pub struct Hp {
     pub max: u64,
     pub current: u64,
+    remainder: f32,  // Damage short of a whole point, carried over to the next hit
 }
 
+impl Hp {
+    pub fn new(max: u64) -> Self {
+        Self { max, current: max, remainder: 0. }
+    }
+    pub fn take(&mut self, damage: f32) {
+        let damage = damage + self.remainder;
+        let whole = damage.floor();
+        self.remainder = damage - whole;
+        self.current = self.current.saturating_sub(whole as u64);
+    }
+}
+
+// Absorbs damage before Hp, and recharges once the unit has gone `regen_delay` seconds without being hit
 #[derive(Component)]
 pub struct Shield {
-    pub max: u64,
-    pub current: u64,
+    pub max: f32,
+    pub current: f32,
+    pub regen_rate: f32,  // Per second
+    pub regen_delay: f32,
+    pub since_damage: f32,
+}
+
+impl Shield {
+    pub fn new(max: f32, regen_rate: f32, regen_delay: f32) -> Self {
+        Self { max, current: max, regen_rate, regen_delay, since_damage: regen_delay }
+    }
+    // Returns the damage that got through
+    pub fn absorb(&mut self, damage: f32) -> f32 {
+        self.since_damage = 0.;
+        let absorbed = damage.min(self.current);
+        self.current -= absorbed;
+        damage - absorbed
+    }
+    pub fn regenerate(&mut self, seconds: f32) {
+        self.since_damage += seconds;
+        if self.since_damage >= self.regen_delay {
+            self.current = (self.current + self.regen_rate * seconds).min(self.max);
+        }
+    }
 }
 
 #[derive(Component)]
@@ -387,7 +532,7 @@ impl Targets {
     pub fn clear(&mut self) {
         self.deque.clear();
     }
-    pub fn get_target(&mut self) -> Option<Entity> {
+    pub fn get_target(&self) -> Option<Entity> {
         if self.deque.len() > 0 {
             return Some(self.deque[0].clone())
         }
@@ -450,6 +595,7 @@ Subunits are translated and rotated by independent systems.
 */
 #[derive(Component)]
 pub struct Subunit {
+    pub name: String,  // The name of the SubunitData the subunit was created from
     pub relative_position: Vec3,
 }
 
@@ -458,13 +604,74 @@ pub struct Subunit {
 pub struct Explosion;
 
 #[derive(Component)]
-pub struct ExplosionToSpawn(pub Vec3);
+pub struct ExplosionToSpawn {
+    pub position: Vec3,
+    pub sheet: String,  // Texture key of the explosion's animated sheet
+    pub size: f32,  // Width in world units
+}
+
+// Plays the secondary explosions of a destroyed unit, scattered around where it died
+#[derive(Component)]
+pub struct DeathSequence {
+    pub timer: Timer,
+    pub remaining: u32,
+    pub position: Vec3,
+    pub radius: f32,
+    pub sheet: String,
+    pub size: f32,
+}
+
+// A piece of a destroyed unit that drifts and fades until its timer runs out
+#[derive(Component)]
+pub struct Debris {
+    pub timer: Timer,
+}
 
+// Steps a TextureAtlasSprite through a range of frames as described by the atlas's TextureData
 #[derive(Component)]
-pub struct ExplosionTimer(pub Timer);
+pub struct AnimatedSprite {
+    pub timer: Timer,
+    pub range: FrameRange,
+    pub loop_mode: LoopMode,
+    pub despawn_when_finished: bool,
+    pub finished: bool,
+    reversing: bool,  // PingPong only
+}
 
-impl Default for ExplosionTimer {
-	fn default() -> Self {
-		Self(Timer::from_seconds(0.05, true))
-	}
+impl AnimatedSprite {
+    pub fn new(texture_data: &TextureData, range: Option<&str>) -> Self {
+        Self {
+            timer: Timer::from_seconds(texture_data.frame_duration, true),
+            range: texture_data.range(range),
+            loop_mode: texture_data.loop_mode,
+            despawn_when_finished: false,
+            finished: false,
+            reversing: false
+        }
+    }
+    // Removes the entity after a single play through
+    pub fn despawning(mut self) -> Self {
+        self.despawn_when_finished = true;
+        self
+    }
+    // The frame after `index`, or None once a single play through is over
+    pub fn next_frame(&mut self, index: usize) -> Option<usize> {
+        let FrameRange { first, last } = self.range;
+        if index < first || index > last {
+            return Some(first)
+        }
+        match self.loop_mode {
+            LoopMode::Once => if index < last { Some(index + 1) } else { None },
+            LoopMode::Loop => Some(if index < last { index + 1 } else { first }),
+            LoopMode::PingPong => {
+                if first == last {
+                    return Some(first)
+                }
+                if (self.reversing && index == first) || (!self.reversing && index == last) {
+                    self.reversing = !self.reversing;
+                }
+                Some(if self.reversing { index - 1 } else { index + 1 })
+            }
+        }
+    }
 }
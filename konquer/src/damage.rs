@@ -0,0 +1,82 @@
+use bevy::prelude::*;
+
+use crate::*;
+
+const POINT_DEFENSE_VS_CAPITAL: f32 = 0.1;  // Point defense is meant for small, fast targets
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DamageType {
+    Ballistic,
+    PointDefense,
+    Explosive
+}
+
+impl DamageType {
+    // The fraction of damage that gets through each armor class
+    pub fn multiplier(&self, armor: ArmorClass) -> f32 {
+        match (self, armor) {
+            (DamageType::PointDefense, ArmorClass::Capital) => POINT_DEFENSE_VS_CAPITAL,
+            _ => 1.  // Ballistic and explosive are even against all armor
+        }
+    }
+}
+
+// Damage about to be dealt to a unit. Every source of damage sends one of these so that
+// resistances, shields and attribution are applied the same way.
+pub struct DamageEvent {
+    pub target: Entity,
+    pub damage: f32,
+    pub damage_per_max_hp: f32,  // Added as a fraction of the target's maximum Hp
+    pub damage_type: DamageType,
+    pub source: Player,
+}
+
+pub fn damage_system(
+    mut commands: Commands,
+    mut ev_damage: EventReader<DamageEvent>,
+    mut q_targets: Query<(&Unit, &mut Hp, Option<&mut Shield>, &UnitStats)>,
+) {
+    for ev in ev_damage.iter() {
+        let (unit, mut hp, mut shield, stats) = match q_targets.get_mut(ev.target) {
+            Ok(target) => target,
+            Err(_) => continue  // Destroyed by an earlier hit
+        };
+        let mut damage = (ev.damage + ev.damage_per_max_hp * hp.max as f32) * ev.damage_type.multiplier(stats.armor);
+        if let Some(shield) = shield.as_mut() {
+            damage = shield.absorb(damage);
+        }
+        hp.take(damage);
+        commands.entity(ev.target).insert(LastAttacker { player: ev.source.clone() });
+        println!("{} took {} damage from Player {} and now has {} hp", unit.name, damage, ev.source.id, hp.current);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn point_defense_wears_down_capitals() {
+        let mut app = App::new();
+        app.init_resource::<Events<DamageEvent>>()
+            .add_system(damage_system);
+        let attacker = Player::new();
+        let target = app.world.spawn()
+            .insert(Unit::new("target".to_string(), Player::new()))
+            .insert(Hp::new(100))
+            .insert(UnitStats { hp: 100, armor: ArmorClass::Capital, ..Default::default() })
+            .id();
+        // pd1 does 1 + 2% of 100 hp, which capital armor cuts to 0.3 a hit
+        for _ in 0..10 {
+            app.world.resource_mut::<Events<DamageEvent>>().send(DamageEvent {
+                target,
+                damage: 1.,
+                damage_per_max_hp: 0.02,
+                damage_type: DamageType::PointDefense,
+                source: attacker.clone(),
+            });
+            app.update();
+        }
+        assert_eq!(app.world.get::<Hp>(target).unwrap().current, 97);
+    }
+}
@@ -1,21 +1,26 @@
+use std::{collections::{BTreeMap, HashMap}, fmt};
+
 use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
 use bevy::prelude::*;
 
-use crate::Subunit;
+use crate::{Subunit, AssemblyData, ProjectileRegistry, DamageType, EXPLOSION_SHEET};
 
 // -- Subunit --------------------------------------------
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
 pub struct SubunitData {
     pub name: String,
     pub class: SubunitClassData,
     pub subclass: String,
+    pub hardpoint_type: HardpointType,
     pub hardpoint_size: i64,
+    pub mass: f32,
     pub size: Vec<f32>,
     pub sprites: Vec<SpriteData>
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
 #[serde(tag="name")]
 pub enum SubunitClassData {
     Turret {
@@ -35,25 +40,52 @@ pub enum SubunitClassData {
         particle_velocity_variance: f32,
         particle_color: Vec<f32>,
         particle_sprite: String,
+    },
+    // Spinal weapons such as railguns are fixed to the hull, which has to be turned to aim them
+    ForwardWeapon {
+        reload_time: u64,
+        fire_range: f32,
+        firing_arc: f32,  // Degrees either side of the nose within which it will fire
+        projectile: String,
+        sources: Vec<Vec<f32>>
+    },
+    ShieldGenerator {
+        capacity: f32,
+        regen_rate: f32,  // Per second
+        regen_delay: f32  // Seconds after taking damage before regenerating
     }
 }
 
 // -- Platform --------------------------------------------
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
 pub struct PlatformData {
     pub name: String,
     pub class: PlatformClassData,
     pub subclass: String,
     pub hp: u64,
+    pub mass: f32,
     pub size: Vec<f32>,
     pub sight_radius: f32,
     pub teamcolor_sprite: SpriteData,
     pub sprites: Vec<SpriteData>,
-    pub hardpoints: Vec<HardpointData>
+    pub hardpoints: Vec<HardpointData>,
+    #[serde(default)]
+    pub armor: ArmorClass,
+    #[serde(default)]
+    pub death: DeathData
+}
+
+// How a platform's hull stands up to each damage type
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all="lowercase")]
+pub enum ArmorClass {
+    Light,
+    #[default]
+    Capital
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
 #[serde(tag="name")]
 pub enum PlatformClassData {
     Capital {
@@ -67,24 +99,115 @@ pub enum PlatformClassData {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+// What is left behind when a unit built on the platform is destroyed
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
+pub struct DeathData {
+    pub explosion: String,  // Texture key of an animated sheet
+    pub scale: f32,  // Explosion size relative to the longest side of the platform
+    #[serde(default)]
+    pub secondary_explosions: u32,  // Smaller explosions scattered over the hull after the first
+    #[serde(default)]
+    pub secondary_interval: f32,  // Seconds between secondary explosions
+    #[serde(default)]
+    pub debris: Vec<DebrisData>
+}
+
+impl Default for DeathData {
+    fn default() -> Self {
+        Self {
+            explosion: EXPLOSION_SHEET.to_string(),
+            scale: 1.,
+            secondary_explosions: 0,
+            secondary_interval: 0.,
+            debris: Vec::new()
+        }
+    }
+}
+
+// Pieces of the hull thrown outwards, keeping the dying unit's velocity
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
+pub struct DebrisData {
+    pub sprite: SpriteData,
+    pub count: u32,
+    pub speed: f32,  // Maximum speed added to the unit's velocity, per tick
+    pub spin: f32,  // Maximum angular velocity, radians per tick
+    pub lifetime: f32  // Seconds
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
 pub struct HardpointData {
-    pub class_name: String,
+    pub id: String,  // Unique within the platform, referenced by assembly loadouts
+    pub hardpoint_type: HardpointType,
     pub hardpoint_size: i64,
     pub z_order: f32,
     pub position: Vec<f32>
 }
 
+impl HardpointData {
+    // A subunit fits a hardpoint of the same type that is at least as large as the subunit
+    pub fn accepts(&self, subunit: &SubunitData) -> Result<(), Incompatibility> {
+        if subunit.hardpoint_type != self.hardpoint_type {
+            return Err(Incompatibility::WrongType { subunit_type: subunit.hardpoint_type, hardpoint_type: self.hardpoint_type })
+        }
+        if subunit.hardpoint_size > self.hardpoint_size {
+            return Err(Incompatibility::TooLarge { subunit_size: subunit.hardpoint_size, hardpoint_size: self.hardpoint_size })
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all="lowercase")]
+pub enum HardpointType {
+    Weapon,
+    Engine,
+    Utility
+}
+
+impl fmt::Display for HardpointType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HardpointType::Weapon => write!(f, "weapon"),
+            HardpointType::Engine => write!(f, "engine"),
+            HardpointType::Utility => write!(f, "utility"),
+        }
+    }
+}
+
+// Why a subunit cannot be fitted to a hardpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Incompatibility {
+    WrongType { subunit_type: HardpointType, hardpoint_type: HardpointType },
+    TooLarge { subunit_size: i64, hardpoint_size: i64 },
+}
+
+impl fmt::Display for Incompatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Incompatibility::WrongType { subunit_type, hardpoint_type } =>
+                write!(f, "{} subunits cannot be fitted to {} hardpoints", subunit_type, hardpoint_type),
+            Incompatibility::TooLarge { subunit_size, hardpoint_size } =>
+                write!(f, "size {} is too large for a size {} hardpoint", subunit_size, hardpoint_size),
+        }
+    }
+}
+
+impl PlatformData {
+    pub fn hardpoint(&self, id: &String) -> Option<&HardpointData> {
+        self.hardpoints.iter().find(|hardpoint| &hardpoint.id == id)
+    }
+}
+
 // Misc
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
 pub struct SpriteData {
     pub texture: String,
     pub size: Vec<f32>,
     pub z_order: f32
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
 pub struct ProjectileData {
     pub name: String,
     pub class: ProjectileClassData,
@@ -92,11 +215,13 @@ pub struct ProjectileData {
     pub velocity: f32,
     pub damage: f32,
     pub range: f32,
+    #[serde(default)]
+    pub interceptable: bool,  // Point defense may shoot it down
     pub size: Vec<f32>,
     pub sprites: Vec<SpriteData>
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
 #[serde(tag="name")]
 pub enum ProjectileClassData {
     Ballistic {
@@ -104,6 +229,38 @@ pub enum ProjectileClassData {
     },
     PointDefense {
         damage_per_max_hp: f32
+    },
+    // Launched at `velocity` and guided towards the target it was fired at until the fuel runs out
+    Missile {
+        damage_per_max_hp: f32,
+        fuel_time: f32,  // Seconds
+        turn_rate: f32,  // Radians per tick
+        acceleration: f32,  // Added to the speed each tick
+        seeker_cone: f32  // Degrees either side of the nose within which the target stays locked
+    }
+}
+
+impl ProjectileClassData {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProjectileClassData::Ballistic { .. } => "Ballistic",
+            ProjectileClassData::PointDefense { .. } => "PointDefense",
+            ProjectileClassData::Missile { .. } => "Missile"
+        }
+    }
+    pub fn damage_type(&self) -> DamageType {
+        match self {
+            ProjectileClassData::Ballistic { .. } => DamageType::Ballistic,
+            ProjectileClassData::PointDefense { .. } => DamageType::PointDefense,
+            ProjectileClassData::Missile { .. } => DamageType::Explosive
+        }
+    }
+    pub fn damage_per_max_hp(&self) -> f32 {
+        match self {
+            ProjectileClassData::Ballistic { damage_per_max_hp }
+            | ProjectileClassData::PointDefense { damage_per_max_hp }
+            | ProjectileClassData::Missile { damage_per_max_hp, .. } => *damage_per_max_hp
+        }
     }
 }
 
@@ -112,25 +269,236 @@ pub enum ProjectileClassData {
 pub struct UnitData {
     pub name: String,
     pub platform: PlatformData,
-    pub loadout: Vec<SubunitData>
+    pub loadout: HashMap<String, SubunitData>,  // Subunits keyed by the id of the hardpoint they are fitted to
+    pub stats: UnitStats,
+    pub build_time: f32,
+    pub cost: u32
 }
 
 impl UnitData {
-    pub fn new(name: String, platform: PlatformData, loadout: Vec<SubunitData>) -> Self {
-        Self {
-            name,
+    pub fn new(assembly: &AssemblyData, platform: PlatformData, loadout: HashMap<String, SubunitData>, projectiles: &ProjectileRegistry) -> Self {
+        let mut unit_data = Self {
+            name: assembly.name.clone(),
             platform,
-            loadout
+            loadout,
+            stats: UnitStats::default(),
+            build_time: assembly.build_time,
+            cost: assembly.cost
+        };
+        unit_data.stats = UnitStats::compute(&unit_data, projectiles);
+        unit_data
+    }
+    // The occupied hardpoints in the order the platform declares them
+    pub fn fitted(&self) -> impl Iterator<Item = (&HardpointData, &SubunitData)> {
+        self.platform.hardpoints.iter().filter_map(|hardpoint| {
+            self.loadout.get(&hardpoint.id).map(|subunit| (hardpoint, subunit))
+        })
+    }
+}
+
+// -- Unit stats ---------------------------
+
+const TURN_RATE_PER_ACCELERATION: f32 = 0.8;
+
+// Totals over a platform and its loadout. Spawned units carry a copy.
+#[derive(Component, Clone, Debug, Default)]
+pub struct UnitStats {
+    pub hp: u64,
+    pub shield: f32,  // Pooled from every fitted shield generator
+    pub shield_regen: f32,
+    pub shield_regen_delay: f32,  // The slowest generator holds up the rest
+    pub armor: ArmorClass,
+    pub mass: f32,
+    pub thrust: f32,
+    pub acceleration: f32,  // Per tick, from the forward thrust of the fitted thrusters
+    pub turn_rate: f32,  // Angular acceleration per tick
+    pub dps: BTreeMap<String, f32>,  // Keyed by projectile class
+    pub max_hp_per_second: f32,  // Fraction of the target's maximum hp dealt each second, on top of dps
+    pub max_range: f32,
+    pub forward_range: f32  // Of the longest ranged forward weapon, or 0 without any
+}
+
+impl UnitStats {
+    pub fn compute(unit_data: &UnitData, projectiles: &ProjectileRegistry) -> Self {
+        let mut stats = UnitStats {
+            hp: unit_data.platform.hp,
+            mass: unit_data.platform.mass,
+            armor: unit_data.platform.armor,
+            ..Default::default()
+        };
+        // Depots have engines built into the platform
+        if let PlatformClassData::Depot { forward_thrust } = unit_data.platform.class {
+            stats.thrust += forward_thrust;
+        }
+        for (_, subunit) in unit_data.fitted() {
+            stats.mass += subunit.mass;
+            match &subunit.class {
+                SubunitClassData::Turret { reload_time, fire_range, projectile, firing_pattern, sources, .. } => {
+                    let shots = match firing_pattern.as_str() {
+                        "alternating" => 1,
+                        _ => sources.len()
+                    };
+                    stats.add_weapon(projectiles, projectile, shots, *reload_time, *fire_range);
+                },
+                SubunitClassData::ForwardWeapon { reload_time, fire_range, projectile, sources, .. } => {
+                    stats.add_weapon(projectiles, projectile, sources.len(), *reload_time, *fire_range);
+                    stats.forward_range = stats.forward_range.max(*fire_range);
+                },
+                SubunitClassData::Thruster { forward_thrust, .. } => {
+                    stats.thrust += forward_thrust;
+                },
+                SubunitClassData::ShieldGenerator { capacity, regen_rate, regen_delay } => {
+                    stats.shield += capacity;
+                    stats.shield_regen += regen_rate;
+                    stats.shield_regen_delay = stats.shield_regen_delay.max(*regen_delay);
+                }
+            }
         }
+        if stats.mass > 0. {
+            stats.acceleration = stats.thrust / stats.mass;
+        }
+        stats.turn_rate = stats.acceleration * TURN_RATE_PER_ACCELERATION;
+        stats
+    }
+
+    fn add_weapon(&mut self, projectiles: &ProjectileRegistry, projectile: &String, shots: usize, reload_time: u64, fire_range: f32) {
+        self.max_range = self.max_range.max(fire_range);
+        // Unresolved projectiles are reported by the loader
+        if let Some(projectile) = projectiles.get(projectile) {
+            let shots_per_second = shots as f32 * 1000. / reload_time.max(1) as f32;
+            *self.dps.entry(projectile.class.name().to_string()).or_insert(0.) += projectile.damage * shots_per_second;
+            self.max_hp_per_second += projectile.class.damage_per_max_hp() * shots_per_second;
+        }
+    }
+
+    pub fn total_dps(&self) -> f32 {
+        self.dps.values().sum()
+    }
+    // Damage per second against a target with `hp` maximum hp, before armor and shields
+    pub fn dps_against(&self, hp: u64) -> f32 {
+        self.total_dps() + self.max_hp_per_second * hp as f32
     }
 }
 
 // -- Texture data ---------------------------
 
-#[derive(Serialize, Deserialize, Clone)]
+// Describes a texture that is split into a grid of frames. The remaining fields are optional and only matter to
+// animated sprites.
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
 pub struct TextureData {
     pub tile_size_x: f32,
     pub tile_size_y: f32,
     pub columns: usize,
     pub rows: usize,
-}
\ No newline at end of file
+    #[serde(default)]
+    pub frames: Option<usize>,  // Defaults to every cell of the grid
+    #[serde(default = "default_frame_duration")]
+    pub frame_duration: f32,  // Seconds
+    #[serde(default)]
+    pub loop_mode: LoopMode,
+    #[serde(default)]
+    pub ranges: BTreeMap<String, FrameRange>  // Named parts of the sheet that can be played on their own
+}
+
+fn default_frame_duration() -> f32 {
+    0.05
+}
+
+impl TextureData {
+    pub fn frame_count(&self) -> usize {
+        self.frames.unwrap_or(self.columns * self.rows).min(self.columns * self.rows)
+    }
+    // Clamps frame counts and ranges that run past the end of the grid and drops ranges that end before they start.
+    // Returns a description of each problem that was fixed.
+    pub fn clamp_to_grid(&mut self) -> Vec<String> {
+        let mut problems: Vec<String> = Vec::new();
+        let cells = self.columns * self.rows;
+        if cells == 0 {
+            problems.push(format!("the {}x{} grid has no frames", self.columns, self.rows));
+        }
+        if let Some(frames) = self.frames.filter(|frames| *frames > cells) {
+            problems.push(format!("frames is {} but the {}x{} grid only has {}", frames, self.columns, self.rows, cells));
+            self.frames = Some(cells);
+        }
+        let last_frame = self.frame_count().max(1) - 1;
+        self.ranges.retain(|name, range| {
+            if range.first > range.last {
+                problems.push(format!("range '{}' starts at frame {}, after its last frame {}", name, range.first, range.last));
+                return false
+            }
+            if range.last > last_frame {
+                problems.push(format!("range '{}' ends at frame {}, past the last frame {}", name, range.last, last_frame));
+                range.last = last_frame;
+                range.first = range.first.min(last_frame);
+            }
+            true
+        });
+        problems
+    }
+    // The frames to play, either a named range or the whole sheet
+    pub fn range(&self, name: Option<&str>) -> FrameRange {
+        name.and_then(|name| self.ranges.get(name).copied())
+            .unwrap_or(FrameRange { first: 0, last: self.frame_count().max(1) - 1 })
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all="lowercase")]
+pub enum LoopMode {
+    #[default]
+    Once,
+    Loop,
+    PingPong
+}
+
+// First and last frame, inclusive
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FrameRange {
+    pub first: usize,
+    pub last: usize
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hardpoints_accept_smaller_subunits_of_their_type() {
+        let hardpoint: HardpointData = serde_json::from_value(serde_json::json!({
+            "id": "fore", "hardpoint_type": "weapon", "hardpoint_size": 2, "z_order": 0.0, "position": [0.0, 0.0, 0.0]
+        })).unwrap();
+        let subunit = |hardpoint_type: &str, hardpoint_size: i64| -> SubunitData {
+            serde_json::from_value(serde_json::json!({
+                "name": "s",
+                "class": {
+                    "name": "Turret", "reload_time": 500, "acceleration": 500.0, "fire_range": 200.0, "angle_on_target": 5.0,
+                    "projectile": "bp1", "firing_pattern": "alternating", "sources": []
+                },
+                "subclass": "s", "hardpoint_type": hardpoint_type, "hardpoint_size": hardpoint_size,
+                "mass": 1.0, "size": [1.0, 1.0], "sprites": []
+            })).unwrap()
+        };
+        assert_eq!(hardpoint.accepts(&subunit("weapon", 1)), Ok(()));
+        assert_eq!(hardpoint.accepts(&subunit("weapon", 2)), Ok(()));
+        assert_eq!(
+            hardpoint.accepts(&subunit("weapon", 3)),
+            Err(Incompatibility::TooLarge { subunit_size: 3, hardpoint_size: 2 })
+        );
+        assert_eq!(
+            hardpoint.accepts(&subunit("engine", 1)),
+            Err(Incompatibility::WrongType { subunit_type: HardpointType::Engine, hardpoint_type: HardpointType::Weapon })
+        );
+    }
+
+    #[test]
+    fn texture_data_is_clamped_to_grid() {
+        let mut texture_data: TextureData = serde_json::from_str(r#"{
+            "tile_size_x": 64.0, "tile_size_y": 64.0, "columns": 2, "rows": 2, "frames": 6,
+            "ranges": { "backwards": { "first": 3, "last": 1 }, "long": { "first": 2, "last": 9 } }
+        }"#).unwrap();
+        assert_eq!(texture_data.clamp_to_grid().len(), 3);
+        assert_eq!(texture_data.frame_count(), 4);
+        assert_eq!(texture_data.range(Some("long")), FrameRange { first: 2, last: 3 });
+        assert_eq!(texture_data.range(Some("backwards")), FrameRange { first: 0, last: 3 });
+        assert!(texture_data.clamp_to_grid().is_empty());
+    }
+}
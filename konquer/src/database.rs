@@ -0,0 +1,1193 @@
+use std::{fs, fmt, collections::{BTreeMap, HashMap, HashSet}, path::{Path, PathBuf}, time::SystemTime};
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use schemars::{JsonSchema, schema::{InstanceType, Metadata, RootSchema, Schema, SchemaObject}, schema_for};
+
+use crate::*;
+
+pub const BASE_PACK: &str = "base";
+pub const MODS_DIR: &str = "mods";
+
+// Layout of the data directories beneath the root of a data pack
+pub const ASSEMBLY_DIR: &str = "data/assemblies";
+pub const SUBUNIT_DIR: &str = "data/subunits";
+pub const PLATFORM_DIR: &str = "data/platforms";
+pub const PROJECTILE_DIR: &str = "data/projectiles";
+pub const UNIT_DATA_DIRS: [&str; 4] = [SUBUNIT_DIR, PLATFORM_DIR, PROJECTILE_DIR, ASSEMBLY_DIR];
+
+// Problems found while reading or assembling unit data
+#[derive(Debug)]
+pub enum LoadError {
+    Io { path: PathBuf, error: std::io::Error },
+    Parse { path: PathBuf, error: ParseError },
+    UnresolvedTemplate { path: PathBuf, template: String },
+    CircularTemplate { path: PathBuf, template: String },
+    UnresolvedPlatform { assembly: String, platform: String },
+    UnresolvedSubunit { assembly: String, subunit: String },
+    UnresolvedProjectile { subunit: String, projectile: String },
+    IncompatibleHardpoint { assembly: String, subunit: String, hardpoint: String, reason: Incompatibility },
+    UnknownHardpoint { assembly: String, hardpoint: String },
+    UnfilledHardpoint { assembly: String, hardpoint: String },
+    DuplicateHardpoint { platform: String, hardpoint: String },
+    MissingTexture { owner: String, texture: String },
+    NotAnAtlas { owner: String, texture: String },
+    InvalidTextureData { path: PathBuf, problem: String },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io { path, error } =>
+                write!(f, "{}: could not be read: {}", path.display(), error),
+            LoadError::Parse { path, error } => match error.location {
+                Some((line, column)) => write!(f, "{}:{}:{}: could not be parsed: {}", path.display(), line, column, error.message),
+                None => write!(f, "{}: could not be parsed: {}", path.display(), error.message)
+            },
+            LoadError::UnresolvedTemplate { path, template } =>
+                write!(f, "{}: extends '{}', which does not exist or failed to load", path.display(), template),
+            LoadError::CircularTemplate { path, template } =>
+                write!(f, "{}: extends '{}', which extends it in turn", path.display(), template),
+            LoadError::UnresolvedPlatform { assembly, platform } =>
+                write!(f, "assembly '{}': unknown platform '{}'", assembly, platform),
+            LoadError::UnresolvedSubunit { assembly, subunit } =>
+                write!(f, "assembly '{}': unknown subunit '{}'", assembly, subunit),
+            LoadError::UnresolvedProjectile { subunit, projectile } =>
+                write!(f, "subunit '{}': unknown projectile '{}'", subunit, projectile),
+            LoadError::IncompatibleHardpoint { assembly, subunit, hardpoint, reason } =>
+                write!(f, "assembly '{}': subunit '{}' does not fit hardpoint '{}': {}", assembly, subunit, hardpoint, reason),
+            LoadError::UnknownHardpoint { assembly, hardpoint } =>
+                write!(f, "assembly '{}': the platform has no hardpoint '{}'", assembly, hardpoint),
+            LoadError::UnfilledHardpoint { assembly, hardpoint } =>
+                write!(f, "assembly '{}': hardpoint '{}' is not in the loadout (use null to leave it empty)", assembly, hardpoint),
+            LoadError::DuplicateHardpoint { platform, hardpoint } =>
+                write!(f, "platform '{}': hardpoint '{}' is declared more than once", platform, hardpoint),
+            LoadError::MissingTexture { owner, texture } =>
+                write!(f, "'{}': texture '{}' does not exist", owner, texture),
+            LoadError::NotAnAtlas { owner, texture } =>
+                write!(f, "'{}': texture '{}' is used as an animated sheet but has no .json grid data beside it", owner, texture),
+            LoadError::InvalidTextureData { path, problem } =>
+                write!(f, "{}: {}", path.display(), problem),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+// A syntax or type error in a data file, at a 1-based line and column when the format reports one
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    pub message: String,
+    pub location: Option<(usize, usize)>,
+}
+
+impl ParseError {
+    fn from_json(error: serde_json::Error) -> Self {
+        let location = if error.line() > 0 { Some((error.line(), error.column())) } else { None };
+        Self { message: strip_location(error.to_string()), location }
+    }
+    fn from_ron(error: ron::Error) -> Self {
+        let location = if error.position.line > 0 { Some((error.position.line, error.position.col)) } else { None };
+        Self { message: error.code.to_string(), location }
+    }
+    fn from_toml(error: toml::de::Error) -> Self {
+        let location = error.line_col().map(|(line, column)| (line + 1, column + 1));
+        Self { message: strip_location(error.to_string()), location }
+    }
+}
+
+// serde_json and toml append the location to their messages, which is reported separately
+fn strip_location(message: String) -> String {
+    match message.rfind(" at line ") {
+        Some(i) => message[..i].to_string(),
+        None => message
+    }
+}
+
+// Unit data may be written in any of these formats, chosen by file extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataFormat {
+    Json,
+    Ron,
+    Toml,
+}
+
+impl DataFormat {
+    pub const EXTENSIONS: [&'static str; 3] = ["json", "ron", "toml"];
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "json" => Some(DataFormat::Json),
+            "ron" => Some(DataFormat::Ron),
+            "toml" => Some(DataFormat::Toml),
+            _ => None
+        }
+    }
+
+    pub fn parse<T: DeserializeOwned>(&self, s: &str) -> Result<T, ParseError> {
+        match self {
+            DataFormat::Json => serde_json::from_str::<T>(s).map_err(ParseError::from_json),
+            DataFormat::Ron => ron::from_str::<T>(s).map_err(ParseError::from_ron),
+            DataFormat::Toml => toml::from_str::<T>(s).map_err(ParseError::from_toml),
+        }
+    }
+
+    // ron cannot deserialize struct fields into string keys directly, so RON goes through its own Value first
+    pub fn parse_value(&self, s: &str) -> Result<Value, ParseError> {
+        match self {
+            DataFormat::Ron => {
+                let value = ron::from_str::<ron::Value>(s).map_err(ParseError::from_ron)?;
+                serde_json::to_value(value).map_err(ParseError::from_json)
+            },
+            _ => self.parse::<Value>(s)
+        }
+    }
+}
+
+pub struct UnitDataCollection {
+    collection: std::collections::HashMap<String, UnitData>,
+}
+
+impl UnitDataCollection {
+    
+    pub fn new() -> Self {
+        Self { collection: std::collections::HashMap::new() }
+    }
+
+    pub fn insert(&mut self, name: String, element: UnitData) {
+        self.collection.insert(name, element);
+    }
+
+    pub fn get(&self, key: &String) -> Option<&UnitData> {
+        self.collection.get(key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &UnitData> {
+        self.collection.values()
+    }
+
+}
+
+// The loadout maps hardpoint ids to subunit names. A null subunit leaves the hardpoint empty.
+#[derive(Default, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct AssemblyData {
+    pub name: String,
+    pub platform: String,
+    pub loadout: BTreeMap<String, Option<String>>,
+    #[serde(default)]
+    pub build_time: f32,  // Seconds a depot spends producing the unit
+    #[serde(default)]
+    pub cost: u32
+}
+
+#[derive(Default)]
+pub struct AssemblyRegistry {
+    assemblies: Vec<AssemblyData>,
+    sources: HashMap<String, String>
+}
+
+impl AssemblyRegistry {
+    pub fn new() -> Self {
+        Self { assemblies: Vec::new(), sources: HashMap::new() }
+    }
+    // Adds an assembly, replacing any earlier assembly with the same name
+    pub fn push(&mut self, item: AssemblyData) {
+        self.assemblies.retain(|assembly| assembly.name != item.name);
+        self.assemblies.push(item);
+    }
+    pub fn push_from(&mut self, pack: &str, item: AssemblyData) {
+        self.sources.insert(item.name.clone(), pack.to_string());
+        self.push(item);
+    }
+    // The name of the data pack an assembly was loaded from
+    pub fn source(&self, key: &String) -> Option<&String> {
+        self.sources.get(key)
+    }
+    pub fn iter(&self) -> std::slice::Iter<'_, AssemblyData> {
+        self.assemblies.iter()
+    }
+}
+
+pub struct SubunitRegistry {
+    collection: std::collections::HashMap<String, SubunitData>,
+    sources: std::collections::HashMap<String, String>
+}
+
+impl SubunitRegistry {
+    pub fn new() -> Self {
+        Self { collection: std::collections::HashMap::new(), sources: std::collections::HashMap::new() }
+    }
+    pub fn insert(&mut self, name: String, element: SubunitData) {
+        self.collection.insert(name, element);
+    }
+    // Inserts an element loaded from the named data pack, replacing any earlier definition
+    pub fn insert_from(&mut self, pack: &str, name: String, element: SubunitData) {
+        self.sources.insert(name.clone(), pack.to_string());
+        self.collection.insert(name, element);
+    }
+    // The name of the data pack an element was loaded from
+    pub fn source(&self, key: &String) -> Option<&String> {
+        self.sources.get(key)
+    }
+    pub fn get(&self, key: &String) -> Option<&SubunitData> {
+        self.collection.get(key)
+    }
+    pub fn values(&self) -> std::collections::hash_map::Values<'_, String, SubunitData> {
+        self.collection.values()
+    }
+}
+
+pub struct PlatformRegistry {
+    collection: std::collections::HashMap<String, PlatformData>,
+    sources: std::collections::HashMap<String, String>
+}
+
+impl PlatformRegistry {
+    pub fn new() -> Self {
+        Self { collection: std::collections::HashMap::new(), sources: std::collections::HashMap::new() }
+    }
+    pub fn insert(&mut self, name: String, element: PlatformData) {
+        self.collection.insert(name, element);
+    }
+    // Inserts an element loaded from the named data pack, replacing any earlier definition
+    pub fn insert_from(&mut self, pack: &str, name: String, element: PlatformData) {
+        self.sources.insert(name.clone(), pack.to_string());
+        self.collection.insert(name, element);
+    }
+    // The name of the data pack an element was loaded from
+    pub fn source(&self, key: &String) -> Option<&String> {
+        self.sources.get(key)
+    }
+    pub fn get(&self, key: &String) -> Option<&PlatformData> {
+        self.collection.get(key)
+    }
+    pub fn values(&self) -> std::collections::hash_map::Values<'_, String, PlatformData> {
+        self.collection.values()
+    }
+}
+
+pub struct ProjectileRegistry {
+    collection: std::collections::HashMap<String, ProjectileData>,
+    sources: std::collections::HashMap<String, String>
+}
+
+impl ProjectileRegistry {
+    pub fn new() -> Self {
+        Self { collection: std::collections::HashMap::new(), sources: std::collections::HashMap::new() }
+    }
+    pub fn insert(&mut self, name: String, element: ProjectileData) {
+        self.collection.insert(name, element);
+    }
+    // Inserts an element loaded from the named data pack, replacing any earlier definition
+    pub fn insert_from(&mut self, pack: &str, name: String, element: ProjectileData) {
+        self.sources.insert(name.clone(), pack.to_string());
+        self.collection.insert(name, element);
+    }
+    // The name of the data pack an element was loaded from
+    pub fn source(&self, key: &String) -> Option<&String> {
+        self.sources.get(key)
+    }
+    pub fn get(&self, key: &String) -> Option<&ProjectileData> {
+        self.collection.get(key)
+    }
+    pub fn values(&self) -> std::collections::hash_map::Values<'_, String, ProjectileData> {
+        self.collection.values()
+    }
+}
+
+
+// A directory of unit data and textures laid out like the base asset directory
+#[derive(Clone, Debug)]
+pub struct DataPack {
+    pub name: String,
+    pub root: PathBuf,
+}
+
+impl DataPack {
+    pub fn new(name: &str, root: &Path) -> Self {
+        Self { name: name.to_string(), root: root.to_path_buf() }
+    }
+    pub fn base(root: &Path) -> Self {
+        Self::new(BASE_PACK, root)
+    }
+}
+
+// Data packs in priority order. Definitions in later packs replace those of the same name in earlier packs.
+#[derive(Clone, Debug)]
+pub struct DataPacks(pub Vec<DataPack>);
+
+impl DataPacks {
+    // The base pack at `asset_dir` followed by every pack in its mods directory, sorted by name
+    pub fn discover(asset_dir: &Path) -> Self {
+        let mut packs: Vec<DataPack> = vec![DataPack::base(asset_dir)];
+        if let Ok(entries) = fs::read_dir(asset_dir.join(MODS_DIR)) {
+            let mut mods: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).filter(|path| path.is_dir()).collect();
+            mods.sort();
+            for root in mods {
+                let name = root.file_name().unwrap_or_default().to_string_lossy().to_string();
+                packs.push(DataPack::new(&name, &root));
+            }
+        }
+        Self(packs)
+    }
+}
+
+// Everything needed to spawn units, loaded from the data directories of one or more data packs
+pub struct UnitDatabase {
+    pub subunits: SubunitRegistry,
+    pub platforms: PlatformRegistry,
+    pub projectiles: ProjectileRegistry,
+    pub assemblies: AssemblyRegistry,
+    pub units: UnitDataCollection,
+}
+
+impl UnitDatabase {
+    // Loads the database from a single asset root, failing with every problem found
+    pub fn load(root: &Path) -> Result<UnitDatabase, Vec<LoadError>> {
+        UnitDatabase::load_packs(&[DataPack::base(root)])
+    }
+
+    pub fn load_partial(root: &Path) -> (UnitDatabase, Vec<LoadError>) {
+        UnitDatabase::load_packs_partial(&[DataPack::base(root)])
+    }
+
+    // Loads the database from layered data packs, failing with every problem found
+    pub fn load_packs(packs: &[DataPack]) -> Result<UnitDatabase, Vec<LoadError>> {
+        let (database, errors) = UnitDatabase::load_packs_partial(packs);
+        if errors.is_empty() {
+            Ok(database)
+        }
+        else {
+            Err(errors)
+        }
+    }
+
+    // Loads everything that can be loaded. Definitions that fail to parse or resolve are left out and returned alongside.
+    pub fn load_packs_partial(packs: &[DataPack]) -> (UnitDatabase, Vec<LoadError>) {
+        let mut errors: Vec<LoadError> = Vec::new();
+        let mut subunits = SubunitRegistry::new();
+        for (pack, data) in read_pack_data::<SubunitData>(packs, SUBUNIT_DIR, &mut errors) {
+            subunits.insert_from(pack, data.name.clone(), data);
+        }
+        let mut platforms = PlatformRegistry::new();
+        for (pack, data) in read_pack_data::<PlatformData>(packs, PLATFORM_DIR, &mut errors) {
+            platforms.insert_from(pack, data.name.clone(), data);
+        }
+        let mut projectiles = ProjectileRegistry::new();
+        for (pack, data) in read_pack_data::<ProjectileData>(packs, PROJECTILE_DIR, &mut errors) {
+            projectiles.insert_from(pack, data.name.clone(), data);
+        }
+        let mut assemblies = AssemblyRegistry::new();
+        for (pack, data) in read_pack_data::<AssemblyData>(packs, ASSEMBLY_DIR, &mut errors) {
+            assemblies.push_from(pack, data);
+        }
+        let mut units = UnitDataCollection::new();
+        assemble_unit_data(&assemblies, &platforms, &subunits, &projectiles, &mut units, &mut errors);
+        check_projectiles(&subunits, &projectiles, &mut errors);
+        check_textures(packs, &subunits, &platforms, &projectiles, &mut errors);
+        (
+            UnitDatabase { subunits, platforms, projectiles, assemblies, units },
+            errors
+        )
+    }
+}
+
+// Tracks the modification times of the data files in a set of data packs
+pub struct DataWatcher {
+    packs: Vec<DataPack>,
+    stamps: HashMap<PathBuf, SystemTime>,
+}
+
+impl DataWatcher {
+    pub fn new(packs: &[DataPack]) -> Self {
+        let mut watcher = Self { packs: packs.to_vec(), stamps: HashMap::new() };
+        watcher.stamps = watcher.read_stamps();
+        watcher
+    }
+    pub fn packs(&self) -> &[DataPack] {
+        &self.packs
+    }
+    // Returns true if any data file was added, removed or modified since the last poll
+    pub fn poll(&mut self) -> bool {
+        let stamps = self.read_stamps();
+        if stamps != self.stamps {
+            self.stamps = stamps;
+            return true
+        }
+        false
+    }
+    fn read_stamps(&self) -> HashMap<PathBuf, SystemTime> {
+        let mut stamps: HashMap<PathBuf, SystemTime> = HashMap::new();
+        for pack in self.packs.iter() {
+            for dir in UNIT_DATA_DIRS {
+                for path in find_data_files(&pack.root.join(dir)) {
+                    if let Ok(modified) = fs::metadata(&path).and_then(|m| m.modified()) {
+                        stamps.insert(path, modified);
+                    }
+                }
+            }
+        }
+        stamps
+    }
+}
+
+// JSON Schemas for every kind of data file, along with the name of the kind
+pub fn data_schemas() -> Vec<(&'static str, Value)> {
+    let mut schemas = vec![
+        ("subunit", schema_for!(SubunitData)),
+        ("platform", schema_for!(PlatformData)),
+        ("projectile", schema_for!(ProjectileData)),
+        ("assembly", schema_for!(AssemblyData)),
+    ];
+    // Unit data files may extend another definition, see `read_pack_data`
+    for (_, schema) in schemas.iter_mut() {
+        schema.schema.object().properties.insert("extends".to_string(), Schema::Object(SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            metadata: Some(Box::new(Metadata {
+                description: Some("The name of a definition of the same kind to start from".to_string()),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }));
+    }
+    let mut schemas: Vec<(&str, Value)> = schemas.into_iter().map(|(kind, schema)| (kind, extendable_schema(schema))).collect();
+    schemas.push(("texture", serde_json::to_value(schema_for!(TextureData)).expect("Schemas should serialize")));
+    schemas
+}
+
+const PARTIAL_PREFIX: &str = "Partial";
+
+// A file with `extends` only sets the fields it changes, at any depth, so it is checked against a copy of the schema
+// in which only `name` and `extends` are required. Every definition gets a partial twin for the copy to refer to.
+fn extendable_schema(schema: RootSchema) -> Value {
+    let mut full = serde_json::to_value(schema).expect("Schemas should serialize");
+    let full = full.as_object_mut().expect("Root schemas are objects");
+    let mut root = serde_json::Map::new();
+    for key in ["$schema", "title", "definitions"] {
+        if let Some(value) = full.remove(key) {
+            root.insert(key.to_string(), value);
+        }
+    }
+    let mut partial = Value::Object(full.clone());
+    make_partial(&mut partial);
+    partial["required"] = serde_json::json!(["name", "extends"]);
+    if let Some(Value::Object(definitions)) = root.get_mut("definitions") {
+        let partials: Vec<(String, Value)> = definitions.iter()
+            .map(|(name, definition)| {
+                let mut definition = definition.clone();
+                make_partial(&mut definition);
+                (format!("{}{}", PARTIAL_PREFIX, name), definition)
+            })
+            .collect();
+        definitions.extend(partials);
+    }
+    root.insert("if".to_string(), serde_json::json!({ "required": ["extends"] }));
+    root.insert("then".to_string(), partial);
+    root.insert("else".to_string(), Value::Object(full.clone()));
+    Value::Object(root)
+}
+
+// Drops every `required` list and points references at the partial definitions. Without required tags every variant
+// of an enum could match, so exactly-one-of becomes any-of.
+fn make_partial(schema: &mut Value) {
+    match schema {
+        Value::Object(object) => {
+            if matches!(object.get("required"), Some(Value::Array(_))) {
+                object.remove("required");
+            }
+            if let Some(one_of) = object.remove("oneOf") {
+                object.insert("anyOf".to_string(), one_of);
+            }
+            if let Some(Value::String(reference)) = object.get_mut("$ref") {
+                if let Some(name) = reference.strip_prefix("#/definitions/") {
+                    *reference = format!("#/definitions/{}{}", PARTIAL_PREFIX, name);
+                }
+            }
+            for value in object.values_mut() {
+                make_partial(value);
+            }
+        },
+        Value::Array(array) => array.iter_mut().for_each(make_partial),
+        _ => ()
+    }
+}
+
+// Every data file beneath `dir` in any supported format, sorted by path
+pub fn find_data_files(dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = DataFormat::EXTENSIONS.iter()
+        .flat_map(|extension| find_files(dir, &format!("*.{}", extension)).flatten())
+        .collect();
+    paths.sort();
+    paths
+}
+
+// Every file beneath `dir`, at any depth, whose name matches `pattern`. `dir` is matched literally even if it contains
+// glob metacharacters.
+pub fn find_files(dir: &Path, pattern: &str) -> glob::Paths {
+    let dir = PathBuf::from(glob::Pattern::escape(&dir.to_string_lossy()));
+    glob::glob(&dir.join("**").join(pattern).to_string_lossy()).expect("Fatal: Invalid pattern")
+}
+
+// The path of `path` relative to `root` with forward slashes, which is how textures and assets are keyed on every
+// platform
+pub fn asset_key(path: &Path, root: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let components: Option<Vec<&str>> = relative.iter().map(|component| component.to_str()).collect();
+    Some(components?.join("/"))
+}
+
+// Deserializes every data file beneath `dir` in each data pack, in pack order, along with the name of the pack it came
+// from. Files may be JSON, RON or TOML, chosen by extension. Files that cannot be read or parsed are skipped and
+// reported in `errors`. Only the first pack must have the directory.
+//
+// A file may declare `"extends": "<name>"` to start from another definition of the same kind and override some of its
+// fields. Objects are merged field by field, anything else (including lists) is replaced. A file extending its own name
+// starts from the definition in an earlier pack.
+pub fn read_pack_data<'a, T: DeserializeOwned>(packs: &'a [DataPack], dir: &str, errors: &mut Vec<LoadError>) -> Vec<(&'a str, T)> {
+    let mut items: Vec<(&str, T)> = Vec::new();
+    let mut documents: Vec<(&str, PathBuf, Value)> = Vec::new();
+    for (i, pack) in packs.iter().enumerate() {
+        let pack_dir = pack.root.join(dir);
+        if !pack_dir.is_dir() {
+            if i == 0 {
+                errors.push(LoadError::Io {
+                    path: pack_dir,
+                    error: std::io::Error::new(std::io::ErrorKind::NotFound, "directory not found")
+                });
+            }
+            continue
+        }
+        for path in find_data_files(&pack_dir) {
+            let format = match DataFormat::from_path(&path) {
+                Some(format) => format,
+                None => continue
+            };
+            match fs::read_to_string(&path) {
+                Ok(s) => {
+                    match format.parse_value(&s) {
+                        Ok(document) => documents.push((pack.name.as_str(), path, document)),
+                        Err(error) => errors.push(LoadError::Parse { path, error })
+                    }
+                },
+                Err(error) => errors.push(LoadError::Io { path, error })
+            }
+        }
+    }
+    let mut templates = TemplateResolver::new(&documents);
+    for (i, (pack, path, _)) in documents.iter().enumerate() {
+        if let Some(document) = templates.resolve(i, errors) {
+            match deserialize_document::<T>(path, document) {
+                Ok(item) => items.push((pack, item)),
+                Err(error) => errors.push(LoadError::Parse { path: path.clone(), error })
+            }
+        }
+    }
+    items
+}
+
+// A step from a value into one of its fields or elements
+#[derive(Clone)]
+enum FieldStep {
+    Key(String),
+    Index(usize),
+}
+
+// Documents are only converted to `T` after templates are merged, so errors carry the path of the offending field
+// rather than a position. The position is recovered by finding that field in the file.
+fn deserialize_document<T: DeserializeOwned>(path: &Path, document: Value) -> Result<T, ParseError> {
+    serde_path_to_error::deserialize::<_, T>(&document).map_err(|error| {
+        let mut steps: Vec<FieldStep> = error.path().iter().filter_map(|segment| match segment {
+            serde_path_to_error::Segment::Map { key } => Some(FieldStep::Key(key.clone())),
+            serde_path_to_error::Segment::Seq { index } => Some(FieldStep::Index(*index)),
+            _ => None
+        }).collect();
+        let inner = error.into_inner().to_string();
+        if let Some(value) = value_at(&document, &steps) {
+            steps.extend(find_unexpected(value, &inner));
+        }
+        let location = fs::read_to_string(path).ok().map(|s| find_field(&s, &steps));
+        let message = if steps.is_empty() { inner } else { format!("{}: {}", format_steps(&steps), inner) };
+        ParseError { message, location }
+    })
+}
+
+fn value_at<'a>(value: &'a Value, steps: &[FieldStep]) -> Option<&'a Value> {
+    steps.iter().try_fold(value, |value, step| match step {
+        FieldStep::Key(key) => value.get(key),
+        FieldStep::Index(index) => value.get(index)
+    })
+}
+
+// Internally tagged enums are buffered before they are deserialized, so type errors inside them stop at the enum's
+// own field. The offending field within it is recovered as the only one holding the value the error complains about.
+fn find_unexpected(value: &Value, message: &str) -> Vec<FieldStep> {
+    let found = match ["invalid type: ", "invalid value: "].iter().find_map(|prefix| message.strip_prefix(prefix)) {
+        Some(rest) => rest.rfind(", expected").map_or(rest, |end| &rest[..end]),
+        None => return Vec::new()
+    };
+    fn describe(value: &Value) -> String {
+        match value {
+            Value::Null => serde::de::Unexpected::Unit,
+            Value::Bool(b) => serde::de::Unexpected::Bool(*b),
+            Value::Number(n) => match (n.as_u64(), n.as_i64()) {
+                (Some(u), _) => serde::de::Unexpected::Unsigned(u),
+                (_, Some(i)) => serde::de::Unexpected::Signed(i),
+                _ => serde::de::Unexpected::Float(n.as_f64().unwrap_or_default())
+            },
+            Value::String(s) => serde::de::Unexpected::Str(s),
+            Value::Array(_) => serde::de::Unexpected::Seq,
+            Value::Object(_) => serde::de::Unexpected::Map,
+        }.to_string()
+    }
+    fn search(value: &Value, found: &str, steps: &mut Vec<FieldStep>, matches: &mut Vec<Vec<FieldStep>>) {
+        let children: Vec<(FieldStep, &Value)> = match value {
+            Value::Object(fields) => fields.iter().map(|(key, value)| (FieldStep::Key(key.clone()), value)).collect(),
+            Value::Array(elements) => elements.iter().enumerate().map(|(i, value)| (FieldStep::Index(i), value)).collect(),
+            _ => Vec::new()
+        };
+        for (step, child) in children {
+            steps.push(step);
+            if describe(child) == found {
+                matches.push(steps.clone());
+            }
+            search(child, found, steps, matches);
+            steps.pop();
+        }
+    }
+    // The value at the error's path may itself be what was unexpected
+    if describe(value) == found {
+        return Vec::new()
+    }
+    let mut matches: Vec<Vec<FieldStep>> = Vec::new();
+    search(value, found, &mut Vec::new(), &mut matches);
+    if matches.len() == 1 { matches.remove(0) } else { Vec::new() }
+}
+
+// Written the way serde_path_to_error writes paths, as in `sprites[1].texture`
+fn format_steps(steps: &[FieldStep]) -> String {
+    let mut s = String::new();
+    for step in steps {
+        match step {
+            FieldStep::Key(key) if s.is_empty() => s.push_str(key),
+            FieldStep::Key(key) => s.push_str(&format!(".{}", key)),
+            FieldStep::Index(index) => s.push_str(&format!("[{}]", index))
+        }
+    }
+    s
+}
+
+// The 1-based line and column of the last field in `steps`, found by searching for each key in turn. A field the
+// file does not set, such as a missing or inherited one, is reported at the nearest enclosing field that it does, or
+// else at the start of the file. Keys are matched in any of the supported formats: `"key":`, `key:`, `key =` and the
+// TOML table headers `[key]` and `[[key]]`. The fields of the n-th element of a list are taken to be the n-th match
+// after the list's own key.
+fn find_field(s: &str, steps: &[FieldStep]) -> (usize, usize) {
+    let is_key = |i: usize, key: &str| {
+        let before = s[..i].chars().next_back();
+        let after = s[i + key.len()..].trim_start_matches('"').trim_start();
+        let in_header = s[..i].rsplit('\n').next().is_some_and(|line| line.trim_start().starts_with('['));
+        !matches!(before, Some(c) if c.is_alphanumeric() || c == '_')
+            && (after.starts_with(':') || after.starts_with('=') || (in_header && (after.starts_with(']') || after.starts_with('.'))))
+    };
+    let mut offset = 0;
+    let mut found = None;
+    let mut skip = 0;
+    for step in steps {
+        match step {
+            FieldStep::Index(index) => skip = *index,
+            FieldStep::Key(key) => {
+                let start = s[offset..].match_indices(key.as_str()).map(|(i, _)| offset + i).filter(|i| is_key(*i, key)).nth(skip);
+                match start {
+                    Some(start) => {
+                        offset = start + key.len();
+                        found = Some(start);
+                        skip = 0;
+                    },
+                    None => break
+                }
+            }
+        }
+    }
+    let start = match found {
+        Some(start) if s[..start].ends_with("[[") => start - 2,
+        Some(start) if s[..start].ends_with('"') || s[..start].ends_with('[') => start - 1,
+        Some(start) => start,
+        None => s.len() - s.trim_start().len()
+    };
+    let line = s[..start].matches('\n').count() + 1;
+    let column = start - s[..start].rfind('\n').map(|i| i + 1).unwrap_or(0) + 1;
+    (line, column)
+}
+
+// Resolves `extends` chains between documents of the same kind
+struct TemplateResolver<'a> {
+    documents: &'a Vec<(&'a str, PathBuf, Value)>,
+    names: HashMap<String, Vec<usize>>,  // Every document declaring a name, in priority order
+    resolved: Vec<Option<Option<Value>>>,  // None until visited, then the merged document or None if it failed
+    chain: Vec<usize>,
+}
+
+impl<'a> TemplateResolver<'a> {
+    fn new(documents: &'a Vec<(&'a str, PathBuf, Value)>) -> Self {
+        let mut names: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, (_, _, document)) in documents.iter().enumerate() {
+            if let Some(name) = document.get("name").and_then(Value::as_str) {
+                names.entry(name.to_string()).or_default().push(i);
+            }
+        }
+        Self { documents, names, resolved: vec![None; documents.len()], chain: Vec::new() }
+    }
+
+    // The document `i` extends: the highest priority definition of `template`, or the previous one if `i` overrides itself
+    fn parent(&self, i: usize, template: &String) -> Option<usize> {
+        let own_name = self.documents[i].2.get("name").and_then(Value::as_str);
+        let candidates = self.names.get(template)?;
+        if own_name == Some(template.as_str()) {
+            candidates.iter().rev().find(|j| **j < i).copied()
+        }
+        else {
+            candidates.last().copied()
+        }
+    }
+
+    fn resolve(&mut self, i: usize, errors: &mut Vec<LoadError>) -> Option<Value> {
+        if let Some(resolved) = &self.resolved[i] {
+            return resolved.clone()
+        }
+        let (_, path, document) = &self.documents[i];
+        let mut document = document.clone();
+        let template = match document.as_object_mut().and_then(|object| object.remove("extends")) {
+            Some(template) => template,
+            None => {
+                self.resolved[i] = Some(Some(document.clone()));
+                return Some(document)
+            }
+        };
+        let template = template.as_str().map(String::from).unwrap_or_else(|| template.to_string());
+        self.chain.push(i);
+        let merged = match self.parent(i, &template) {
+            Some(parent) if self.chain.contains(&parent) => {
+                errors.push(LoadError::CircularTemplate { path: path.clone(), template });
+                None
+            },
+            Some(parent) => {
+                match self.resolve(parent, errors) {
+                    Some(mut base) => {
+                        merge_json(&mut base, document);
+                        Some(base)
+                    },
+                    None => {
+                        errors.push(LoadError::UnresolvedTemplate { path: path.clone(), template });
+                        None
+                    }
+                }
+            },
+            None => {
+                errors.push(LoadError::UnresolvedTemplate { path: path.clone(), template });
+                None
+            }
+        };
+        self.chain.pop();
+        self.resolved[i] = Some(merged.clone());
+        merged
+    }
+}
+
+fn merge_json(base: &mut Value, overrides: Value) {
+    match (base, overrides) {
+        (Value::Object(base), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                merge_json(base.entry(key).or_insert(Value::Null), value);
+            }
+        },
+        (base, overrides) => *base = overrides
+    }
+}
+
+// Resolves each assembly against the registries. Assemblies that cannot be resolved are left out of `unit_data`.
+pub fn assemble_unit_data(
+    assembly_registry: &AssemblyRegistry,
+    platform_registry: &PlatformRegistry,
+    subunit_registry: &SubunitRegistry,
+    projectile_registry: &ProjectileRegistry,
+    unit_data: &mut UnitDataCollection,
+    errors: &mut Vec<LoadError>,
+) {
+    'assemblies: for assembly in assembly_registry.iter() {
+        if let Some(platform) = platform_registry.get(&assembly.platform) {
+            let mut ids: HashSet<&String> = HashSet::new();
+            for hardpoint in platform.hardpoints.iter() {
+                if !ids.insert(&hardpoint.id) {
+                    errors.push(LoadError::DuplicateHardpoint { platform: platform.name.clone(), hardpoint: hardpoint.id.clone() });
+                    continue 'assemblies
+                }
+                if !assembly.loadout.contains_key(&hardpoint.id) {
+                    // The hardpoint is left empty, but this is almost always a typo
+                    errors.push(LoadError::UnfilledHardpoint { assembly: assembly.name.clone(), hardpoint: hardpoint.id.clone() });
+                }
+            }
+            let mut loadout: HashMap<String, SubunitData> = HashMap::new();
+            for (hardpoint_id, subunit_name) in assembly.loadout.iter() {
+                let hardpoint = match platform.hardpoint(hardpoint_id) {
+                    Some(hardpoint) => hardpoint,
+                    None => {
+                        errors.push(LoadError::UnknownHardpoint { assembly: assembly.name.clone(), hardpoint: hardpoint_id.clone() });
+                        continue 'assemblies
+                    }
+                };
+                let subunit_name = match subunit_name {
+                    Some(subunit_name) => subunit_name,
+                    None => continue  // Deliberately empty
+                };
+                if let Some(subunit) = subunit_registry.get(subunit_name) {
+                    // Verify that the hardpoint fits the subunit
+                    match hardpoint.accepts(subunit) {
+                        Ok(()) => {
+                            loadout.insert(hardpoint_id.clone(), subunit.clone());
+                        },
+                        Err(reason) => {
+                            errors.push(LoadError::IncompatibleHardpoint {
+                                assembly: assembly.name.clone(),
+                                subunit: subunit_name.clone(),
+                                hardpoint: hardpoint_id.clone(),
+                                reason
+                            });
+                            continue 'assemblies
+                        }
+                    }
+                }
+                else {
+                    errors.push(LoadError::UnresolvedSubunit { assembly: assembly.name.clone(), subunit: subunit_name.clone() });
+                    continue 'assemblies  // Give up on loading this Unit
+                }
+            }
+            unit_data.insert(
+                assembly.name.clone(),
+                UnitData::new(assembly, platform.clone(), loadout, projectile_registry)
+            )
+        }
+        else {
+            errors.push(LoadError::UnresolvedPlatform { assembly: assembly.name.clone(), platform: assembly.platform.clone() });
+        }
+    }
+}
+
+// Reports turrets that fire projectiles missing from the registry
+pub fn check_projectiles(
+    subunit_registry: &SubunitRegistry,
+    projectile_registry: &ProjectileRegistry,
+    errors: &mut Vec<LoadError>,
+) {
+    for subunit in subunit_registry.values() {
+        if let SubunitClassData::Turret { projectile, .. } | SubunitClassData::ForwardWeapon { projectile, .. } = &subunit.class {
+            if projectile_registry.get(projectile).is_none() {
+                errors.push(LoadError::UnresolvedProjectile { subunit: subunit.name.clone(), projectile: projectile.clone() });
+            }
+        }
+    }
+}
+
+// Reports sprites whose textures do not exist in any data pack
+pub fn check_textures(
+    packs: &[DataPack],
+    subunit_registry: &SubunitRegistry,
+    platform_registry: &PlatformRegistry,
+    projectile_registry: &ProjectileRegistry,
+    errors: &mut Vec<LoadError>,
+) {
+    let mut check = |owner: &String, texture: &String, atlas: bool| {
+        // The last pack to provide a texture is the one that gets loaded
+        match packs.iter().rev().map(|pack| pack.root.join(texture)).find(|path| path.is_file()) {
+            None => errors.push(LoadError::MissingTexture { owner: owner.clone(), texture: texture.clone() }),
+            Some(path) if atlas && !path.with_extension("json").is_file() =>
+                errors.push(LoadError::NotAnAtlas { owner: owner.clone(), texture: texture.clone() }),
+            Some(_) => ()
+        }
+    };
+    for subunit in subunit_registry.values() {
+        for sprite in subunit.sprites.iter() {
+            check(&subunit.name, &sprite.texture, false);
+        }
+        if let SubunitClassData::Thruster { particle_sprite, .. } = &subunit.class {
+            check(&subunit.name, particle_sprite, false);
+        }
+    }
+    for platform in platform_registry.values() {
+        check(&platform.name, &platform.teamcolor_sprite.texture, false);
+        for sprite in platform.sprites.iter() {
+            check(&platform.name, &sprite.texture, false);
+        }
+        check(&platform.name, &platform.death.explosion, true);
+        for debris in platform.death.debris.iter() {
+            check(&platform.name, &debris.sprite.texture, false);
+        }
+    }
+    for projectile in projectile_registry.values() {
+        for sprite in projectile.sprites.iter() {
+            check(&projectile.name, &sprite.texture, false);
+        }
+    }
+}
+
+// Checks the grid data beside each texture in the packs. Texture data is loaded with the textures rather than the unit
+// data, so this is not part of loading the database.
+pub fn check_texture_data(packs: &[DataPack], errors: &mut Vec<LoadError>) {
+    for pack in packs.iter() {
+        for path in find_files(&pack.root, "*.png").flatten() {
+            if !in_nested_pack(packs, pack, &path) {
+                read_texture_data(&pack.root, &path, errors);
+            }
+        }
+    }
+}
+
+// Whether `path`, found beneath `pack`, lies in another pack nested inside it and so belongs to that pack instead
+pub fn in_nested_pack(packs: &[DataPack], pack: &DataPack, path: &Path) -> bool {
+    packs.iter().any(|other| other.root != pack.root && other.root.starts_with(&pack.root) && path.starts_with(&other.root))
+}
+
+// The grid data in the .json file beside a texture, if it has one. Files that cannot be read or parsed are reported
+// and the texture is treated as a plain image. Files in the unit data directories belong to the unit loader, even
+// when one shares its name with a texture.
+pub fn read_texture_data(pack_root: &Path, texture_path: &Path, errors: &mut Vec<LoadError>) -> Option<TextureData> {
+    let path = texture_path.with_extension("json");
+    if !path.is_file() || UNIT_DATA_DIRS.iter().any(|dir| path.starts_with(pack_root.join(dir))) {
+        return None
+    }
+    let s = match fs::read_to_string(&path) {
+        Ok(s) => s,
+        Err(error) => {
+            errors.push(LoadError::Io { path, error });
+            return None
+        }
+    };
+    match DataFormat::Json.parse::<TextureData>(&s) {
+        Ok(mut texture_data) => {
+            for problem in texture_data.clamp_to_grid() {
+                errors.push(LoadError::InvalidTextureData { path: path.clone(), problem });
+            }
+            Some(texture_data)
+        },
+        Err(error) => {
+            errors.push(LoadError::Parse { path, error });
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn documents(values: Vec<Value>) -> Vec<(&'static str, PathBuf, Value)> {
+        values.into_iter().enumerate().map(|(i, value)| (BASE_PACK, PathBuf::from(format!("{}.json", i)), value)).collect()
+    }
+
+    #[test]
+    fn merge_json_overrides_fields() {
+        let mut base = serde_json::json!({ "name": "a", "hp": 100, "loadout": { "fore": "turret1", "aft": "turret1" }, "size": [1, 2] });
+        merge_json(&mut base, serde_json::json!({ "hp": 200, "loadout": { "aft": "pdc1" }, "size": [3] }));
+        assert_eq!(base, serde_json::json!({ "name": "a", "hp": 200, "loadout": { "fore": "turret1", "aft": "pdc1" }, "size": [3] }));
+    }
+
+    #[test]
+    fn templates_are_merged() {
+        let documents = documents(vec![
+            serde_json::json!({ "name": "base", "hp": 100, "mass": 10 }),
+            serde_json::json!({ "name": "heavy", "extends": "base", "hp": 300 }),
+        ]);
+        let mut errors: Vec<LoadError> = Vec::new();
+        let resolved = TemplateResolver::new(&documents).resolve(1, &mut errors);
+        assert!(errors.is_empty());
+        assert_eq!(resolved, Some(serde_json::json!({ "name": "heavy", "hp": 300, "mass": 10 })));
+    }
+
+    #[test]
+    fn circular_templates_are_reported() {
+        let documents = documents(vec![
+            serde_json::json!({ "name": "a", "extends": "b" }),
+            serde_json::json!({ "name": "b", "extends": "a" }),
+        ]);
+        let mut errors: Vec<LoadError> = Vec::new();
+        assert_eq!(TemplateResolver::new(&documents).resolve(0, &mut errors), None);
+        assert!(errors.iter().any(|error| matches!(error, LoadError::CircularTemplate { .. })), "{:?}", errors);
+    }
+
+    #[test]
+    fn unresolved_templates_are_reported() {
+        let documents = documents(vec![serde_json::json!({ "name": "a", "extends": "missing" })]);
+        let mut errors: Vec<LoadError> = Vec::new();
+        assert_eq!(TemplateResolver::new(&documents).resolve(0, &mut errors), None);
+        assert!(
+            matches!(errors.as_slice(), [LoadError::UnresolvedTemplate { template, .. }] if template == "missing"),
+            "{:?}", errors
+        );
+    }
+
+    const TURRET_RON: &str = r#"(
+    name: "turret1",
+    class: (
+        name: "Turret",
+        reload_time: 500,
+        acceleration: 500,
+        fire_range: 200.0,
+        angle_on_target: 5.0,
+        projectile: "bp1",
+        firing_pattern: "alternating",
+        sources: [[96.0, 68.0], [96.0, -68.0]],
+    ),
+    subclass: "Ballistic",
+    hardpoint_type: "weapon",
+    hardpoint_size: 2,
+    mass: 20.0,
+    size: [168.0, 162.0],
+    sprites: [(
+        texture: "data/subunits/turret1/turret1.png",
+        size: [168.0, 162.0],
+        z_order: 0,
+    )],
+)
+"#;
+
+    const TURRET_TOML: &str = r#"name = "turret1"
+subclass = "Ballistic"
+hardpoint_type = "weapon"
+hardpoint_size = 2
+mass = 20.0
+size = [168.0, 162.0]
+
+[class]
+name = "Turret"
+reload_time = 500
+acceleration = 500
+fire_range = 200.0
+angle_on_target = 5.0
+projectile = "bp1"
+firing_pattern = "alternating"
+sources = [[96.0, 68.0], [96.0, -68.0]]
+
+[[sprites]]
+texture = "data/subunits/turret1/turret1.png"
+size = [168.0, 162.0]
+z_order = 0
+"#;
+
+    // Loads a single subunit file through the same path as the game
+    fn load_subunit(file_name: &str, contents: &str) -> Result<SubunitData, ParseError> {
+        let root = std::env::temp_dir().join(format!("konquer-{}-{}", file_name.replace('.', "-"), std::process::id()));
+        let path = root.join(SUBUNIT_DIR).join(file_name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, contents).unwrap();
+        let mut errors: Vec<LoadError> = Vec::new();
+        let packs = [DataPack::base(&root)];
+        let mut items = read_pack_data::<SubunitData>(&packs, SUBUNIT_DIR, &mut errors);
+        fs::remove_dir_all(&root).unwrap();
+        match (items.pop(), errors.pop()) {
+            (Some((_, item)), None) => Ok(item),
+            (None, Some(LoadError::Parse { error, .. })) => Err(error),
+            _ => panic!("{} did not load or fail with one parse error", file_name)
+        }
+    }
+
+    #[test]
+    fn ron_and_toml_parse_like_json() {
+        let json = serde_json::to_value(load_subunit("turret1.json", include_str!("../../assets/data/subunits/turret1/turret1.json")).ok().unwrap()).unwrap();
+        for (file_name, contents) in [("turret1.ron", TURRET_RON), ("turret1.toml", TURRET_TOML)] {
+            let subunit = load_subunit(file_name, contents).unwrap_or_else(|error| panic!("{}: {}", file_name, error.message));
+            assert_eq!(serde_json::to_value(subunit).unwrap(), json, "{}", file_name);
+        }
+    }
+
+    #[test]
+    fn parse_errors_are_located() {
+        let json = include_str!("../../assets/data/subunits/turret1/turret1.json");
+        for (file_name, contents, location, message) in [
+            // Syntax errors, located by each format's own parser
+            ("syntax.json", json.replace("\"mass\": 20.0,", "\"mass\": 20.0"), (20, 5), "expected `,`"),
+            ("syntax.ron", TURRET_RON.replace("mass: 20.0,", "mass: 20.0"), (17, 5), ""),
+            ("syntax.toml", TURRET_TOML.replace("mass = 20.0", "mass = "), (5, 8), ""),
+            // Missing fields, at the object they are missing from
+            ("missing.json", json.replace("    \"mass\": 20.0,\n", ""), (1, 1), "missing field `mass`"),
+            ("missing.toml", TURRET_TOML.replace("reload_time = 500\n", ""), (8, 1), "class: missing field `reload_time`"),
+            // Type errors inside internally tagged enums, at the offending field
+            ("tagged.json", json.replace("\"reload_time\": 500", "\"reload_time\": \"fast\""), (5, 9), "class.reload_time: invalid type"),
+            ("tagged.ron", TURRET_RON.replace("reload_time: 500", "reload_time: \"fast\""), (5, 9), "class.reload_time: invalid type"),
+            ("tagged.toml", TURRET_TOML.replace("reload_time = 500", "reload_time = \"fast\""), (10, 1), "class.reload_time: invalid type"),
+            // Type errors in TOML arrays of tables, at the right element
+            ("sprites.toml", format!("{}\n[[sprites]]\ntexture = 7\nsize = [1.0, 1.0]\nz_order = 0\n", TURRET_TOML), (24, 1), "sprites[1].texture: invalid type"),
+        ] {
+            let error = match load_subunit(file_name, &contents) {
+                Ok(_) => panic!("{} loaded", file_name),
+                Err(error) => error
+            };
+            assert_eq!(error.location, Some(location), "{}: {}", file_name, error.message);
+            assert!(error.message.starts_with(message), "{}: {}", file_name, error.message);
+        }
+    }
+
+    #[test]
+    fn unit_data_is_not_texture_data() {
+        let root = std::env::temp_dir().join(format!("konquer-texture-data-{}", std::process::id()));
+        for (path, contents) in [
+            ("data/subunits/thruster1/thruster1.png", ""),
+            ("data/subunits/thruster1/thruster1.json", "{ \"name\": "),
+            ("data/fx/sheet.png", ""),
+            ("data/fx/sheet.json", "{ \"columns\": "),
+        ] {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        let mut errors: Vec<LoadError> = Vec::new();
+        check_texture_data(&[DataPack::base(&root)], &mut errors);
+        fs::remove_dir_all(&root).unwrap();
+        assert!(
+            matches!(errors.as_slice(), [LoadError::Parse { path, .. }] if path.ends_with("fx/sheet.json")),
+            "{:?}", errors
+        );
+    }
+
+    fn validator(kind: &str) -> jsonschema::JSONSchema {
+        let schemas: HashMap<&str, Value> = data_schemas().into_iter().collect();
+        jsonschema::JSONSchema::compile(&schemas[kind]).unwrap()
+    }
+
+    #[test]
+    fn schemas_check_fixtures() {
+        let schema = validator("projectile");
+        let projectile = serde_json::json!({
+            "name": "shell", "class": { "name": "Ballistic", "damage_per_max_hp": 0.0 }, "subclass": "shell",
+            "velocity": 10.0, "damage": 5.0, "range": 200.0, "size": [8.0, 8.0], "sprites": []
+        });
+        assert!(schema.is_valid(&projectile));
+
+        let mut missing_field = projectile.clone();
+        missing_field.as_object_mut().unwrap().remove("damage");
+        assert!(!schema.is_valid(&missing_field));
+
+        let mut wrong_type = projectile.clone();
+        wrong_type["damage"] = serde_json::json!("lots");
+        assert!(!schema.is_valid(&wrong_type));
+
+        let mut wrong_class = projectile.clone();
+        wrong_class["class"]["name"] = serde_json::json!("Laser");
+        assert!(!schema.is_valid(&wrong_class));
+
+        // Definitions that extend another only need what they change, but what they give must still be well typed
+        assert!(schema.is_valid(&serde_json::json!({ "name": "heavy_shell", "extends": "shell", "damage": 10.0 })));
+        assert!(!schema.is_valid(&serde_json::json!({ "name": "heavy_shell", "extends": "shell", "damage": "lots" })));
+        assert!(!schema.is_valid(&serde_json::json!({ "name": "heavy_shell", "damage": 10.0 })));
+    }
+
+    #[test]
+    fn assets_match_schemas() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..").join(ASSET_DIR);
+        for (kind, dir) in [("subunit", SUBUNIT_DIR), ("platform", PLATFORM_DIR), ("projectile", PROJECTILE_DIR), ("assembly", ASSEMBLY_DIR)] {
+            let schema = validator(kind);
+            for path in find_data_files(&root.join(dir)) {
+                let document: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+                assert!(schema.is_valid(&document), "{} does not match the {} schema", path.display(), kind);
+            }
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::*;
+
+pub const STARTING_CREDITS: f32 = 1000.;
+const INCOME_PER_RADIUS: f32 = 0.05;  // Credits per second for each unit of a satellite's radius
+
+// Moons are worth less than planets of the same size
+fn class_income_scale(class: &str) -> f32 {
+    match class {
+        "Planet" => 1.,
+        "Moon" => 0.5,
+        _ => 0.
+    }
+}
+
+// Credits earned per second by the owner of a satellite
+pub fn satellite_income(satellite: &EnvironmentalSatellite) -> f32 {
+    satellite.radius * INCOME_PER_RADIUS * class_income_scale(&satellite.class)
+}
+
+pub struct Stockpile {
+    pub credits: f32,
+    pub income: f32,  // Credits per second from the last income tick
+}
+
+impl Default for Stockpile {
+    fn default() -> Stockpile {
+        Stockpile { credits: STARTING_CREDITS, income: 0. }
+    }
+}
+
+// Every player's stockpile, created the first time the player earns or spends
+#[derive(Default)]
+pub struct PlayerEconomy {
+    stockpiles: HashMap<u8, Stockpile>,
+}
+
+impl PlayerEconomy {
+    pub fn new() -> PlayerEconomy {
+        PlayerEconomy::default()
+    }
+    pub fn credits(&self, player: &Player) -> f32 {
+        self.stockpiles.get(&player.id).map_or(STARTING_CREDITS, |stockpile| stockpile.credits)
+    }
+    pub fn income(&self, player: &Player) -> f32 {
+        self.stockpiles.get(&player.id).map_or(0., |stockpile| stockpile.income)
+    }
+    pub fn deposit(&mut self, player: &Player, credits: f32) {
+        self.stockpile_mut(player).credits += credits;
+    }
+    // Takes `cost` from the player's stockpile if they can afford it
+    pub fn try_spend(&mut self, player: &Player, cost: u32) -> bool {
+        let stockpile = self.stockpile_mut(player);
+        if stockpile.credits < cost as f32 {
+            return false
+        }
+        stockpile.credits -= cost as f32;
+        true
+    }
+    fn stockpile_mut(&mut self, player: &Player) -> &mut Stockpile {
+        self.stockpiles.entry(player.id).or_default()
+    }
+}
+
+pub fn planet_income_system(
+    mut economy: ResMut<PlayerEconomy>,
+    q_satellites: Query<(&EnvironmentalSatellite, &Owner)>,
+) {
+    let mut incomes: HashMap<u8, (Player, f32)> = HashMap::new();
+    for (satellite, owner) in q_satellites.iter() {
+        if let Some(player) = &owner.player {
+            incomes.entry(player.id).or_insert((player.clone(), 0.)).1 += satellite_income(satellite);
+        }
+    }
+    for stockpile in economy.stockpiles.values_mut() {
+        stockpile.income = 0.;
+    }
+    for (player, income) in incomes.into_values() {
+        economy.deposit(&player, income * UNIT_TICK);
+        economy.stockpile_mut(&player).income = income;
+    }
+}
@@ -18,6 +18,7 @@ pub const ORBITAL_RADIUS_RATIO: f32 = 15.;  // The ratio of a Planet's radius to
 pub const MOON_TO_PLANET_RATIO: f32 = 2.;  // The ratio of a Planet's size to the maximum size of its moons
 
 pub const PLANET_ILLUM_SPRITE: &str = "data/environment/planet_lighting_1.png";
+pub const BACKGROUND_DIR: &str = "data/bg/";
 
 
 // TODO fix dimensions
@@ -84,6 +85,8 @@ pub fn environment_startup_system(
         ec_planet
         .insert(PrimarySatellite { gravity_radius: *gravity_radius } )
         .insert(Orbiter)
+        .insert(Owner::default())
+        .insert(Capture::default())
         .insert(EnvironmentalSatellite {
             name: planet_name.to_string(),
             class: "Planet".to_string(),
@@ -120,6 +123,7 @@ pub fn environment_startup_system(
                 ec_moon
                 .insert(SecondarySatellite)
                 .insert(Orbiter)
+                .insert(Owner::default())
                 .insert(EnvironmentalSatellite {
                     name: planet_name.to_string() + " " + &i.to_string(),  // TODO generative moon names
                     class: "Moon".to_string(),
@@ -213,6 +217,13 @@ pub fn environment_appearance_startup_system(
                         .with_alignment(TextAlignment::CENTER),
                     ..default()
                 });
+                // Filled in by ui_planet_system while the label is shown
+                parent.spawn_bundle(Text2dBundle {
+                    text: Text::from_section("", TextStyle { font_size: 20.0, ..text_style.clone() })
+                        .with_alignment(TextAlignment::CENTER),
+                    transform: Transform::from_translation(Vec3::new(0., -30., 0.)),
+                    ..default()
+                }).insert(PlanetOwnerText { planet: entity });
             }).insert(PlanetInfoUI);
 
         });
@@ -269,27 +280,24 @@ pub fn background_startup_system(
 ) {
     let window = windows.get_primary().unwrap();
     let window_size = Vec2::new(window.width(), window.height());
-    'texture: for (i, entry) in glob::glob(&("**/bg/*.png")).expect("Fatal: Invalid pattern").enumerate() {
-        match entry {
-            Ok(path) => {
-                if let Some(s) = path.to_str() {
-                    let path_s = String::from(s).replace("\"", "").replace("assets\\", "").replace("\\", "/");
-                    println!("Adding background layer {}: {}", i, path_s);
-                    commands.spawn().insert(Background { layer: i }).insert_bundle(SpriteBundle {
-                        texture: texture_server.get(&path_s).typed::<Image>(),
-                        sprite: Sprite {
-                            custom_size: Some(Vec2::ONE * 2560.), // All background tiles must be 2560x2560
-                            ..Default::default()
-                        },
-                        transform: Transform {
-                            translation: Vec3::new(0., 0., BACKGROUND_ZORDER),
-                            ..Default::default()
-                        },
-                        ..Default::default()
-                    });
-                }
+    // Layers are drawn in the order of their texture keys
+    let mut layers: Vec<&String> = texture_server.keys()
+        .filter(|key| key.starts_with(BACKGROUND_DIR) && key.ends_with(".png"))
+        .collect();
+    layers.sort();
+    for (i, key) in layers.into_iter().enumerate() {
+        println!("Adding background layer {}: {}", i, key);
+        commands.spawn().insert(Background { layer: i }).insert_bundle(SpriteBundle {
+            texture: texture_server.get(key).typed::<Image>(),
+            sprite: Sprite {
+                custom_size: Some(Vec2::ONE * 2560.), // All background tiles must be 2560x2560
+                ..Default::default()
             },
-            Err(e) => eprintln!("{:?}", e)
-        }
+            transform: Transform {
+                translation: Vec3::new(0., 0., BACKGROUND_ZORDER),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
     }
 }
@@ -12,6 +12,7 @@ use rand::Rng;
 
 use std::ops::Div;
 use std::time::Duration;
+use std::collections::HashSet;
 use std::{sync::atomic::{AtomicU8, Ordering}, fmt::{self}, f32::consts::PI};
 
 pub mod data;
@@ -32,6 +33,12 @@ use inputs::{InputPlugin, MouseOverEvent};
 pub mod camera;
 pub use camera::*;
 
+pub mod database;
+pub use database::*;
+
+pub mod rusted_warfare;
+pub use rusted_warfare::*;
+
 pub mod loader;
 pub use loader::*;
 
@@ -44,6 +51,21 @@ pub use environment::*;
 pub mod animation;
 pub use animation::*;
 
+pub mod production;
+pub use production::*;
+
+pub mod economy;
+pub use economy::*;
+
+pub mod capture;
+pub use capture::*;
+
+pub mod victory;
+pub use victory::*;
+
+pub mod damage;
+pub use damage::*;
+
 // Package level variables
 static NUMBER_OF_OWNERS: AtomicU8 = AtomicU8::new(0);
 
@@ -51,6 +73,7 @@ const DEBUG_GRAPHICS: bool = false;
 
 // TODO parameterize and IO
 const UI_ABOVE_ZORDER: f32 = 500.;
+const EXPLOSION_ZORDER: f32 = 200.;
 const PROJECTILE_ZORDER: f32 = 150.;
 const UNIT_ZORDER: f32 = 100.;
 const THRUSTER_PARTICLE_ZORDER: f32 = 25.;
@@ -64,6 +87,8 @@ const MAP_H: i32 = 2 * 16384;
 
 const SPRITE_SCALE: f32 = 0.01;
 
+pub const UNIT_TICK: f32 = 1. / 60.;  // Seconds per unit update. Per-tick rates such as velocities are tuned to it.
+
 const USER_ID: u8 = 0;
 
 pub struct UnitPlugin;
@@ -77,31 +102,49 @@ enum Stage {
 
 impl Plugin for UnitPlugin {
     fn build(&self, app: &mut App) {
+        // Apps may insert their own MatchState to play by other victory rules
+        if !app.world.contains_resource::<MatchState>() {
+            app.insert_resource(MatchState::default());
+        }
         app
             .insert_resource(Msaa { samples: 1 })
             .add_plugin(ShapePlugin)
             .add_plugin(InputPlugin)
             .add_plugin(AssetLoaderPlugin)
+            .insert_resource(PlayerEconomy::new())
             .add_event::<SpawnUnitEvent>()
+            .add_event::<MatchEnded>()
+            .add_event::<DamageEvent>()
             .add_event::<MouseOverEvent>()
             .add_startup_system(startup_system)
             .add_startup_system_to_stage(StartupStage::Startup, environment_startup_system)
             .add_startup_system_to_stage(StartupStage::Startup, camera_startup_system)
             .add_startup_system_to_stage(StartupStage::PostStartup, background_startup_system)
             .add_startup_system_to_stage(StartupStage::PostStartup, environment_appearance_startup_system)
-            .add_system_set(SystemSet::new()  // Unit updates
-                .with_run_criteria(FixedTimestep::step(1. / 60.))
+            .add_system_set(SystemSet::new()  // Unit updates, until the match is over
+                .with_run_criteria(FixedTimestep::step(UNIT_TICK as f64).chain(match_running_criteria))
                 .with_system(turret_track_and_fire_system).label(Stage::Kinematics)
                 .with_system(capital_movement_system).label(Stage::Kinematics)
                 .with_system(primary_satellite_orbit_system)
                 .with_system(secondary_satellite_orbit_system)
                 // .with_system(tertiary_satellite_orbit_system).after(secondary_satellite_orbit_system)
+                .with_system(missile_guidance_system.before(projectile_movement_system))
                 .with_system(projectile_movement_system)
                 .with_system(capital_ship_repulsion_system)
-                .with_system(capital_ship_destruction_system)
+                .with_system(unit_destruction_system)
+                .with_system(debris_system)
                 .with_system(projectile_collision_system)
+                .with_system(damage_system.after(projectile_collision_system))
+                .with_system(shield_regen_system)
                 .with_system(capital_pathing_system)
-                // Graphics
+                .with_system(planet_income_system)
+                .with_system(planet_capture_system)
+                .with_system(moon_ownership_system)
+                .with_system(production_system)
+                .with_system(match_state_system)
+            )
+            .add_system_set(SystemSet::new()  // Graphics
+                .with_run_criteria(FixedTimestep::step(UNIT_TICK as f64))
                 .with_system(ui_highlight_selected_system)
                 .with_system(ui_show_path_system)
                 .with_system(ui_show_hp_system)
@@ -115,7 +158,8 @@ impl Plugin for UnitPlugin {
             .add_system(thruster_particle_emitter_system)
             .add_system(thruster_particle_system)
             .add_system(explosion_to_spawn_system)
-            .add_system(explosion_animation_system)
+            .add_system(animated_sprite_system)
+            .add_system(death_sequence_system)
             // Mechanics
             .add_system(spawn_units_system)
             // .add_system(teamcolor_system) TODO
@@ -130,6 +174,13 @@ pub struct Player {
     teamcolor: Color
 }
 
+// Players are the same player if they share an id, whatever their other settings
+impl PartialEq for Player {
+    fn eq(&self, other: &Player) -> bool {
+        self.id == other.id
+    }
+}
+
 impl Player {
     pub fn new() -> Player {
         // Create a unique Player ID each time new is called
@@ -156,9 +207,7 @@ const APPROACH_THRESHOLD_REAR: f32 = 100.;
 const APPROACH_THRESHOLD_OMNI: f32 = 5.;
 const THRESH_ARRIVAL: f32 = 50.;
 const APPROACH_THRESH: f32 = 3000.;
-
-const PRIMARY_ACCELERATION: f32 = 0.01;
-const RADIAL_ACCELERATION: f32 = 0.008;
+const HULL_AIM_GAIN: f32 = 0.05;  // Angular velocity per radian off target when aiming forward weapons
 
 fn capital_movement_system(
     mut query: Query<(&mut Transform, &mut Body, &Velocity), Or<(With<Unit>, With<Subunit>)>>,
@@ -179,9 +228,10 @@ fn capital_movement_system(
 }
 
 fn capital_pathing_system(
-    mut query: Query<(&mut UnitPath, &Body, &mut Velocity), With<UnitPath>>,
+    mut query: Query<(&mut UnitPath, &Body, &mut Velocity, &UnitStats, &Targets)>,
+    q_body: Query<&Body>,
 ) {
-    for (mut path, body, mut velocity ) in query.iter_mut() {
+    for (mut path, body, mut velocity, stats, targets) in query.iter_mut() {
         if !path.path.is_empty() {  // For units with a destination
             let dist_to_dest = (path.path[0] - body.position.truncate()).length();
             let target = (path.path[0] - body.position.truncate()).normalize();
@@ -195,9 +245,9 @@ fn capital_pathing_system(
             let pointing_err = ((1. - pointing.dot(target)) / 2.).min(0.);  // The angle between the ship's nose and the target -> [0, 1]
             velocity.dw += 
             if cross > 0.0 {
-                -RADIAL_ACCELERATION * pointing_err.max(0.001).powf(1. / 3.)
+                -stats.turn_rate * pointing_err.max(0.001).powf(1. / 3.)
             } else if cross < 0.0 {
-                RADIAL_ACCELERATION * pointing_err.max(0.001).powf(1. / 3.)
+                stats.turn_rate * pointing_err.max(0.001).powf(1. / 3.)
             } else {
                 0.
             };
@@ -205,10 +255,9 @@ fn capital_pathing_system(
             // velocity.dx += target.x * 0.0003;
             // velocity.dy += target.y * 0.0003;
             if cross.abs() < HEADING_THRESH_BURN {  // If we are close enough to the right heading to use rear thrusters
-                // TODO get values from thrusters
                 // Rear thrusters
-                velocity.dx += (pointing.x * PRIMARY_ACCELERATION);
-                velocity.dy += (pointing.y * PRIMARY_ACCELERATION);
+                velocity.dx += (pointing.x * stats.acceleration);
+                velocity.dy += (pointing.y * stats.acceleration);
                 // velocity.dy += (heading.y * 0.0001) * (dist_to_dest / APPROACH_THRESHOLD_REAR).max(1.);
             }
             if dist_to_dest < body.collision_radius {
@@ -235,6 +284,15 @@ fn capital_pathing_system(
             velocity.dx *= DRAG_LATERAL;
             velocity.dy *= DRAG_LATERAL;
             velocity.dw *= DRAG_RADIAL;
+            // and bring any forward weapons to bear on the current target
+            if stats.forward_range > 0. {
+                if let Some(target_body) = targets.get_target().and_then(|target| q_body.get(target).ok()) {
+                    let target = target_body.position.truncate() - body.position.truncate();
+                    let pointing = Vec2::new(f32::cos(body.position.z), f32::sin(body.position.z));
+                    let desired_dw = pointing.angle_between(target) * HULL_AIM_GAIN;
+                    velocity.dw += (desired_dw - velocity.dw).clamp(-stats.turn_rate, stats.turn_rate);
+                }
+            }
         }
     }
 
@@ -307,6 +365,7 @@ fn thruster_particle_emitter_system(
 
 const TURRET_ON_TARGET_THRESH: f32 = 0.001;  // radians
 const TURRET_FIRE_THRESH: f32 = 10. * PI / 180.;  // radians
+const INTERCEPT_RADIUS: f32 = 2.;  // Point defense rounds burst when they pass this close to an interceptable projectile
 
 fn turret_track_and_fire_system(
     mut commands: Commands,
@@ -317,6 +376,7 @@ fn turret_track_and_fire_system(
     q_body: Query<&Body>,
     q_unit: Query<&Unit>,
     q_velocity: Query<&Velocity, Without<Subunit>>,
+    q_interceptable: Query<(Entity, &Projectile, &Body), With<Interceptable>>,
     q_debug_graphics: Query<Entity, With<DebugTurretTargetLine>>,
     time: Res<Time>,
 ) {
@@ -330,15 +390,36 @@ fn turret_track_and_fire_system(
         let mut targets = q_targets.get_mut(turret_parent.get()).unwrap();
         let parent_velocity: &Velocity = q_velocity.get(turret_parent.get()).unwrap();
         let turret_parent_body = q_body.get(turret_parent.get()).unwrap();
-        if let Some(target_entity) = targets.get_target() {
+        let abs_turret_pos = get_absolute_position(turret_body.position, turret_parent_body.position);
+        // Point defense engages the nearest incoming projectile before anything on the target list
+        let point_defense = projectiles.get(&turret.projectile)
+            .is_some_and(|projectile_data| projectile_data.class.damage_type() == DamageType::PointDefense);
+        let intercept = if point_defense {
+            q_interceptable.iter()
+                .filter(|(_, projectile, _)| projectile.player != parent_unit.player)
+                .map(|(entity, _, body)| (entity, body.position.truncate().distance(abs_turret_pos.truncate())))
+                .filter(|(_, distance)| *distance < turret.range)
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(entity, _)| entity)
+        } else { None };
+        if let Some(target_entity) = intercept.or_else(|| targets.get_target()) {
             if let Ok(target_body) = q_body.get(target_entity) {
                 let heading = Vec2::new(f32::cos(turret_body.position.z + turret_parent_body.position.z), f32::sin(turret_body.position.z + turret_parent_body.position.z));
-                let abs_turret_pos = get_absolute_position(turret_body.position, turret_parent_body.position);
                 let distance_to_target = (target_body.position.truncate() - abs_turret_pos.truncate()).length();
-                let target = (target_body.position.truncate() - abs_turret_pos.truncate()).normalize();
+                // Projectiles are too small and fast to hit by aiming straight at them, so point defense leads its shots
+                let aim_point = match (intercept, q_velocity.get(target_entity), projectiles.get(&turret.projectile)) {
+                    (Some(_), Ok(target_velocity), Some(projectile_data)) => intercept_point(
+                        abs_turret_pos.truncate(),
+                        target_body.position.truncate(),
+                        Vec2::new(target_velocity.dx, target_velocity.dy),
+                        projectile_data.velocity
+                    ),
+                    _ => target_body.position.truncate()
+                };
+                let target = (aim_point - abs_turret_pos.truncate()).normalize();
                 let cross = target.x * heading.y - target.y * heading.x;
                 let err = 1. - heading.dot(target);
-                if cross.abs() > TURRET_ON_TARGET_THRESH {
+                if turret.fixed_arc.is_none() && cross.abs() > TURRET_ON_TARGET_THRESH {
                     turret_velocity.dw = 
                     if cross > 0.0 {
                         -0.1 * err.sqrt().min(1.)
@@ -348,8 +429,15 @@ fn turret_track_and_fire_system(
                         0.
                     };  
                 }
+                // Fixed weapons wait for the hull to be turned onto the target
+                let on_target = match turret.fixed_arc {
+                    Some(firing_arc) => heading.angle_between(target).abs() < firing_arc,
+                    // Interceptions hold fire until the round would pass close enough to burst
+                    None if intercept.is_some() => cross.abs() * (aim_point - abs_turret_pos.truncate()).length() < INTERCEPT_RADIUS,
+                    None => cross.abs() < TURRET_FIRE_THRESH
+                };
                 turret.tick(time.delta());
-                if cross.abs() < TURRET_FIRE_THRESH && turret.ready() && distance_to_target < turret.range {
+                if on_target && turret.ready() && distance_to_target < turret.range {
                     // Fire!
                     if let Some(projectile_data) = projectiles.get(&turret.projectile) {
                         let mut fire_projectile = |fire_from: Vec3| {
@@ -372,8 +460,22 @@ fn turret_track_and_fire_system(
                                 fired_from: fire_from.truncate(),
                                 range: projectile_data.range,
                                 player: parent_unit.player.clone(),
-                                damage: projectile_data.damage
+                                damage: projectile_data.damage,
+                                damage_per_max_hp: projectile_data.class.damage_per_max_hp(),
+                                damage_type: projectile_data.class.damage_type()
                             });
+                            if projectile_data.interceptable {
+                                ec.insert(Interceptable);
+                            }
+                            if let ProjectileClassData::Missile { fuel_time, turn_rate, acceleration, seeker_cone, .. } = projectile_data.class {
+                                ec.insert(Missile {
+                                    target: Some(target_entity),
+                                    fuel: Timer::from_seconds(fuel_time, false),
+                                    turn_rate,
+                                    acceleration,
+                                    seeker_cone: seeker_cone.to_radians()
+                                });
+                            }
                             ec
                             .insert(Body::new(fire_from, Vec2::new(projectile_data.size[0], projectile_data.size[1])))
                             .insert(Velocity {
@@ -451,13 +553,23 @@ fn turret_track_and_fire_system(
     }
 }
 
-fn capital_ship_destruction_system(
+fn unit_destruction_system(
     mut commands: Commands,
-    q_capitals: Query<(Entity, &Body, &Hp), With<CapitalShip>>
+    mut match_state: ResMut<MatchState>,
+    q_capitals: Query<(Entity, &Unit, &Body, &Hp, &Velocity)>,
+    q_attackers: Query<&LastAttacker>,
+    units: Res<UnitDataCollection>,
+    texture_server: Res<TextureServer>
 ) {
-    for (entity, body, hp) in q_capitals.iter() {
+    for (entity, unit, body, hp, velocity) in q_capitals.iter() {
         if hp.current == 0 {
-            commands.spawn().insert(ExplosionToSpawn(body.position));
+            // Whoever landed the last hit gets the credit, unless it was friendly fire
+            if let Some(attacker) = q_attackers.get(entity).ok().filter(|attacker| attacker.player != unit.player) {
+                println!("{} was destroyed by Player {}", unit.name, attacker.player.id);
+                match_state.credit_kill(&attacker.player, units.get(&unit.name).map_or(0., |unit_data| unit_data.cost as f32));
+            }
+            let death = units.get(&unit.name).map(|unit_data| unit_data.platform.death.clone()).unwrap_or_default();
+            spawn_death_effects(&mut commands, &death, body, Some(velocity), &texture_server);
             commands.entity(entity).despawn_recursive();
         }
     }
@@ -467,8 +579,10 @@ fn capital_ship_destruction_system(
 fn projectile_collision_system(
     mut commands: Commands,
     q_debug: Query<Entity, With<DebugProjectileCollisionCheckLine>>,
-    mut q_units: Query<(Entity, &Unit, &mut Hp, &Body), With<Unit>>,
-    q_projectiles: Query<(Entity, &Projectile, &Body), With<Projectile>>,
+    q_units: Query<(Entity, &Unit, &Body)>,
+    q_projectiles: Query<(Entity, &Projectile, &Body, &Velocity)>,
+    q_interceptable: Query<(), With<Interceptable>>,
+    mut ev_damage: EventWriter<DamageEvent>,
 ) { 
     if DEBUG_GRAPHICS {
         for line in q_debug.iter() {
@@ -476,22 +590,68 @@ fn projectile_collision_system(
         }
     }
     let mut qtree = CollisionQuadtree::new(0, Rectangle2D { x: 0., y: 0., width: MAP_W as f32, height: MAP_H as f32 });
-    for (entity, _, body) in q_projectiles.iter() {
-        qtree.insert(EntityBody { entity: entity, position: body.position.truncate(), radius: body.collision_radius })
+    for (entity, _, body, velocity) in q_projectiles.iter() {
+        // Large enough to hold everywhere the projectile has been over the last tick
+        let speed = Vec2::new(velocity.dx, velocity.dy).length();
+        qtree.insert(EntityBody { entity: entity, position: body.position.truncate(), radius: body.collision_radius + speed })
     }
     let mut colliders: Vec<EntityBody> = Vec::new();
-    for (unit_e, unit, mut unit_hp, unit_body) in q_units.iter_mut() {
+    let mut spent: HashSet<Entity> = HashSet::new();  // Projectiles that have already hit something this tick
+
+    // Point defense rounds destroy the interceptable projectiles they pass close to.
+    // Both move several times their own size each tick, so the test is over the whole tick rather than where they are now.
+    for (pd_e, pd, pd_body, pd_velocity) in q_projectiles.iter() {
+        if pd.damage_type != DamageType::PointDefense {
+            continue
+        }
+        let pd_velocity = Vec2::new(pd_velocity.dx, pd_velocity.dy);
+        colliders.clear();
+        qtree.retrieve(pd_body.position.truncate(), pd_body.collision_radius + pd_velocity.length() + INTERCEPT_RADIUS, &mut colliders);
+        for projectile_eb in colliders.iter() {
+            if spent.contains(&pd_e) {
+                break
+            }
+            if spent.contains(&projectile_eb.entity) || !q_interceptable.contains(projectile_eb.entity) {
+                continue
+            }
+            if let Ok((projectile_e, projectile, projectile_body, projectile_velocity)) = q_projectiles.get(projectile_eb.entity) {
+                let distance = closest_approach(
+                    pd_body.position.truncate(),
+                    pd_velocity,
+                    projectile_body.position.truncate(),
+                    Vec2::new(projectile_velocity.dx, projectile_velocity.dy)
+                );
+                if projectile.player != pd.player && distance < (projectile_body.collision_radius + pd_body.collision_radius + INTERCEPT_RADIUS) {
+                    commands.entity(projectile_e).despawn_recursive();
+                    commands.entity(pd_e).despawn_recursive();
+                    spent.insert(projectile_e);
+                    spent.insert(pd_e);
+                }
+            }
+        }
+    }
+
+    for (unit_e, unit, unit_body) in q_units.iter() {
         colliders.clear();
         qtree.retrieve(unit_body.position.truncate(), unit_body.collision_radius, &mut colliders);
         for projectile_eb in colliders.iter() {
-            if let Ok((projectile_e, projectile, projectile_body)) = q_projectiles.get(projectile_eb.entity) {
+            if spent.contains(&projectile_eb.entity) {
+                continue
+            }
+            if let Ok((projectile_e, projectile, projectile_body, _)) = q_projectiles.get(projectile_eb.entity) {
                 if unit.player.id != projectile.player.id {  // Friendly fire off
                     
                     let distance = unit_body.position.truncate().distance(projectile_body.position.truncate());
                     if distance < (projectile_body.collision_radius + unit_body.collision_radius) {
-                        unit_hp.current = (unit_hp.current as f32 - projectile.damage) as u64;
-                        println!("Unit now has {} hp", unit_hp.current);
+                        ev_damage.send(DamageEvent {
+                            target: unit_e,
+                            damage: projectile.damage,
+                            damage_per_max_hp: projectile.damage_per_max_hp,
+                            damage_type: projectile.damage_type,
+                            source: projectile.player.clone()
+                        });
                         commands.entity(projectile_e).despawn_recursive();
+                        spent.insert(projectile_e);
                     }
                     if DEBUG_GRAPHICS {
                         let mut path_builder = PathBuilder::new();
@@ -513,12 +673,22 @@ fn projectile_collision_system(
     }
 }
 
-// Capital ships do not collide, but instead repel one another
+fn shield_regen_system(
+    mut q_shields: Query<&mut Shield>,
+) {
+    for mut shield in q_shields.iter_mut() {
+        shield.regenerate(UNIT_TICK);
+    }
+}
+
+// Capital ships and depots do not collide, but instead repel one another
+type Repelled = Or<(With<CapitalShip>, With<Depot>)>;
+
 fn capital_ship_repulsion_system(
     mut commands: Commands,
     q_debug: Query<Entity, With<DebugCollisionCheckLine>>,
-    q_capitals: Query<(Entity, &Body), With<CapitalShip>>,
-    mut q_velocity: Query<&mut Velocity, With<CapitalShip>>,
+    q_capitals: Query<(Entity, &Body), Repelled>,
+    mut q_velocity: Query<&mut Velocity, Repelled>,
 ) { 
     if DEBUG_GRAPHICS {
         for line in q_debug.iter() {
@@ -567,6 +737,40 @@ fn capital_ship_repulsion_system(
     }
 }
 
+// Burns fuel and turns each missile towards its target. Missiles out of fuel or without a lock fly straight on.
+fn missile_guidance_system(
+    mut q_missiles: Query<(&mut Missile, &mut Body, &mut Velocity)>,
+    q_body: Query<&Body, Without<Missile>>,
+) {
+    for (mut missile, mut body, mut velocity) in q_missiles.iter_mut() {
+        if missile.fuel.finished() {
+            continue
+        }
+        missile.fuel.tick(Duration::from_secs_f32(UNIT_TICK));
+        let direction = Vec2::new(velocity.dx, velocity.dy);
+        let speed = direction.length() + missile.acceleration;
+        let mut heading = direction.y.atan2(direction.x);
+        if let Some(target) = missile.target {
+            match q_body.get(target) {
+                Ok(target_body) => {
+                    let to_target = target_body.position.truncate() - body.position.truncate();
+                    let err = (to_target.y.atan2(to_target.x) - heading + PI).rem_euclid(2. * PI) - PI;
+                    if err.abs() > missile.seeker_cone {
+                        missile.target = None;
+                    }
+                    else {
+                        heading += err.clamp(-missile.turn_rate, missile.turn_rate);
+                    }
+                },
+                Err(_) => missile.target = None  // The target has been destroyed
+            }
+        }
+        velocity.dx = heading.cos() * speed;
+        velocity.dy = heading.sin() * speed;
+        body.position.z = heading.rem_euclid(2. * PI);
+    }
+}
+
 fn projectile_movement_system(
     mut commands: Commands,
     mut query: Query<(Entity, &Projectile, &mut Transform, &mut Body, &Velocity), With<Projectile>>,
@@ -600,6 +804,36 @@ pub fn rotate_vector(p: Vec2, w: f32) -> Vec2 {
     )
 }
 
+// Where a projectile fired at `speed` from `from` meets a target holding its velocity.
+// Targets that cannot be caught are aimed at directly.
+pub fn intercept_point(from: Vec2, target: Vec2, target_velocity: Vec2, speed: f32) -> Vec2 {
+    let offset = target - from;
+    let a = target_velocity.length_squared() - speed * speed;
+    let b = 2. * offset.dot(target_velocity);
+    let c = offset.length_squared();
+    let t = if a.abs() < f32::EPSILON {
+        -c / b
+    } else {
+        let discriminant = b * b - 4. * a * c;
+        if discriminant < 0. {
+            return target
+        }
+        let root = discriminant.sqrt();
+        [(-b - root) / (2. * a), (-b + root) / (2. * a)].into_iter()
+            .filter(|t| *t > 0.)
+            .fold(f32::INFINITY, f32::min)
+    };
+    if t.is_finite() && t > 0. { target + target_velocity * t } else { target }
+}
+
+// The closest two bodies came over the last tick, from where they are now and how far they moved
+pub fn closest_approach(p1: Vec2, v1: Vec2, p2: Vec2, v2: Vec2) -> f32 {
+    let dp = p1 - p2;
+    let dv = v1 - v2;
+    let t = if dv.length_squared() > 0. { (-dp.dot(dv) / dv.length_squared()).clamp(-1., 0.) } else { 0. };
+    (dp + dv * t).length()
+}
+
 pub fn get_absolute_position(subunit_position: Vec3, parent_position: Vec3) -> Vec3 {
     let mut abs_pos: Vec3 = Vec3::from(parent_position);
     abs_pos.x += subunit_position.x * f32::cos(parent_position.z) - subunit_position.y * f32::sin(parent_position.z);
@@ -607,3 +841,140 @@ pub fn get_absolute_position(subunit_position: Vec3, parent_position: Vec3) -> V
     abs_pos.z += subunit_position.z;
     return abs_pos
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::utils::Instant;
+
+    fn load_projectile(json: &str) -> ProjectileData {
+        serde_json::from_str(json).unwrap()
+    }
+
+    // Just the systems that move units and projectiles, with damage events left uncleared so a test can read every one
+    fn combat_app() -> App {
+        let mut projectiles = ProjectileRegistry::new();
+        for json in [
+            include_str!("../../assets/data/projectiles/pd1/pd1.json"),
+            include_str!("../../assets/data/projectiles/bp1/bp1.json"),
+            include_str!("../../assets/data/projectiles/m1/m1.json"),
+        ] {
+            let projectile = load_projectile(json);
+            projectiles.insert(projectile.name.clone(), projectile);
+        }
+        let mut app = App::new();
+        app.insert_resource(projectiles)
+            .insert_resource(TextureServer::new())
+            .insert_resource(Time::default())
+            .init_resource::<Events<DamageEvent>>()
+            .add_system(turret_track_and_fire_system)
+            .add_system(capital_movement_system.after(turret_track_and_fire_system))
+            .add_system(missile_guidance_system.after(capital_movement_system))
+            .add_system(projectile_movement_system.after(missile_guidance_system))
+            .add_system(projectile_collision_system.after(projectile_movement_system));
+        app
+    }
+
+    // A ship at the origin with one loaded pdc1 facing along +x
+    fn spawn_point_defense_ship(app: &mut App, player: &Player) -> Entity {
+        let mut turret = Turret::new("pdc1".to_string(), "pd1".to_string(), 50., 200, "alternating".to_string(), vec![Vec2::new(80., 50.), Vec2::new(80., -50.)]);
+        turret.tick(Duration::from_millis(200));
+        app.world.spawn()
+            .insert(Unit::new("ship1".to_string(), player.clone()))
+            .insert(Targets::new())
+            .insert(Velocity { dx: 0., dy: 0., dw: 0. })
+            .insert(Body::new(Vec3::ZERO, Vec2::new(1392., 732.)))
+            .insert_bundle(SpatialBundle::default())
+            .with_children(|parent| {
+                parent.spawn()
+                    .insert(turret)
+                    .insert(Subunit { name: "pdc1".to_string(), relative_position: Vec3::ZERO })
+                    .insert(Velocity { dx: 0., dy: 0., dw: 0. })
+                    .insert(Body::new(Vec3::ZERO, Vec2::new(120., 126.)))
+                    .insert_bundle(SpatialBundle::default());
+            })
+            .id()
+    }
+
+    fn spawn_incoming(app: &mut App, projectile_data: &ProjectileData, player: &Player, position: Vec2, velocity: Vec2) -> Entity {
+        app.world.spawn()
+            .insert(Projectile {
+                fired_from: position,
+                range: projectile_data.range,
+                player: player.clone(),
+                damage: projectile_data.damage,
+                damage_per_max_hp: projectile_data.class.damage_per_max_hp(),
+                damage_type: projectile_data.class.damage_type()
+            })
+            .insert(Interceptable)
+            .insert(Body::new(position.extend(velocity.y.atan2(velocity.x)), Vec2::new(projectile_data.size[0], projectile_data.size[1])))
+            .insert(Velocity { dx: velocity.x, dy: velocity.y, dw: 0. })
+            .insert_bundle(SpatialBundle::default())
+            .id()
+    }
+
+    // Steps the app until `entity` is gone, returning whether it went within `ticks`
+    fn run_until_despawned(app: &mut App, entity: Entity, ticks: u32) -> bool {
+        let start = Instant::now();
+        for tick in 1..=ticks {
+            app.world.resource_mut::<Time>().update_with_instant(start + Duration::from_secs_f32(UNIT_TICK * tick as f32));
+            app.update();
+            if app.world.get_entity(entity).is_none() {
+                return true
+            }
+        }
+        false
+    }
+
+    #[test]
+    fn point_defense_shoots_down_shell() {
+        let mut app = combat_app();
+        let (defender, attacker) = (Player::new(), Player::new());
+        spawn_point_defense_ship(&mut app, &defender);
+        let bp1 = load_projectile(include_str!("../../assets/data/projectiles/bp1/bp1.json"));
+        // Crossing the turret's front at an angle, bound for the hull
+        let shell = spawn_incoming(&mut app, &bp1, &attacker, Vec2::new(40., 20.), Vec2::new(-40., -20.).normalize() * bp1.velocity);
+
+        assert!(run_until_despawned(&mut app, shell, 60), "the shell was never stopped");
+        assert!(app.world.resource::<Events<DamageEvent>>().is_empty(), "the shell reached the hull");
+    }
+
+    #[test]
+    fn point_defense_shoots_down_missile() {
+        let mut app = combat_app();
+        let (defender, attacker) = (Player::new(), Player::new());
+        let ship = spawn_point_defense_ship(&mut app, &defender);
+        let m1 = load_projectile(include_str!("../../assets/data/projectiles/m1/m1.json"));
+        // Launched across the ship's bow and steering onto it
+        let missile = spawn_incoming(&mut app, &m1, &attacker, Vec2::new(45., 15.), Vec2::new(-m1.velocity, 0.));
+        if let ProjectileClassData::Missile { fuel_time, turn_rate, acceleration, seeker_cone, .. } = m1.class {
+            app.world.entity_mut(missile).insert(Missile {
+                target: Some(ship),
+                fuel: Timer::from_seconds(fuel_time, false),
+                turn_rate,
+                acceleration,
+                seeker_cone: seeker_cone.to_radians()
+            });
+        }
+
+        assert!(run_until_despawned(&mut app, missile, 120), "the missile was never stopped");
+        assert!(app.world.resource::<Events<DamageEvent>>().is_empty(), "the missile reached the hull");
+    }
+
+    #[test]
+    fn intercept_point_leads_moving_target() {
+        // A target crossing at 3 is met by a speed 4 shot one unit of time later
+        let point = intercept_point(Vec2::ZERO, Vec2::new(4., -3.), Vec2::new(0., 3.), 4.);
+        assert!(point.distance(Vec2::new(4., 0.)) < 1e-4);
+        // Too fast to catch, so aim straight at it
+        assert_eq!(intercept_point(Vec2::ZERO, Vec2::new(4., 0.), Vec2::new(10., 0.), 1.), Vec2::new(4., 0.));
+    }
+
+    #[test]
+    fn closest_approach_sweeps_the_tick() {
+        // Two bodies that crossed paths during the tick but are now far apart
+        assert!(closest_approach(Vec2::new(2., 0.), Vec2::new(4., 0.), Vec2::new(-2., 0.), Vec2::new(-4., 0.)) < 1e-4);
+        // Bodies that have not yet met only count where they are now
+        assert_eq!(closest_approach(Vec2::new(-2., 0.), Vec2::new(1., 0.), Vec2::new(2., 0.), Vec2::new(-1., 0.)), 4.);
+    }
+}
@@ -3,122 +3,95 @@ use serde_json;
 use serde::{Deserialize, Serialize};
 use glob;
 
-use std::{fs, collections::HashMap, iter::zip};
+use std::{fs, collections::{BTreeMap, BTreeSet, HashMap}, path::{Path, PathBuf}, sync::Mutex};
 
-use bevy::prelude::*;
+use bevy::{
+    prelude::*,
+    asset::{AssetServerSettings, FileAssetIo},
+    reflect::TypeUuid,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat}
+};
 
 use crate::*;
 
 pub struct AssetLoaderPlugin;
 
-pub struct UnitDataCollection {
-    collection: std::collections::HashMap<String, UnitData>,
-}
-
-impl UnitDataCollection {
-    
-    pub fn new() -> Self {
-        Self { collection: std::collections::HashMap::new() }
-    }
-
-    pub fn insert(&mut self, name: String, element: UnitData) {
-        self.collection.insert(name, element);
-    }
-
-    pub fn get(&self, key: &String) -> Option<&UnitData> {
-        self.collection.get(key)
-    }
-
-}
-
-#[derive(Default, Debug, Serialize, Deserialize)]
-pub struct AssemblyData {
-    name: String,
-    platform: String,
-    loadout: Vec<String>
-}
-
-#[derive(Default)]
-pub struct AssemblyRegistry {
-    assemblies: Vec<AssemblyData>,
-}
-
-impl AssemblyRegistry {
-    pub fn new() -> Self {
-        Self { assemblies: Vec::new() }
-    }
-    pub fn push(&mut self, item: AssemblyData) {
-        self.assemblies.push(item);
-    }
-}
-
-pub struct SubunitRegistry {
-    collection: std::collections::HashMap<String, SubunitData>
-}
-
-impl SubunitRegistry {
-    pub fn new() -> Self {
-        Self { collection: std::collections::HashMap::new() }
-    }
-    pub fn insert(&mut self, name: String, element: SubunitData) {
-        self.collection.insert(name, element);
-    }
-    pub fn get(&self, key: &String) -> Option<&SubunitData> {
-        self.collection.get(key)
-    }
-}
-
-pub struct PlatformRegistry {
-    collection: std::collections::HashMap<String, PlatformData>
-}
-
-impl PlatformRegistry {
-    pub fn new() -> Self {
-        Self { collection: std::collections::HashMap::new() }
-    }
-    pub fn insert(&mut self, name: String, element: PlatformData) {
-        self.collection.insert(name, element);
-    }
-    pub fn get(&self, key: &String) -> Option<&PlatformData> {
-        self.collection.get(key)
-    }
-}
-
-pub struct ProjectileRegistry {
-    collection: std::collections::HashMap<String, ProjectileData>
-}
-
-impl ProjectileRegistry {
-    pub fn new() -> Self {
-        Self { collection: std::collections::HashMap::new() }
-    }
-    pub fn insert(&mut self, name: String, element: ProjectileData) {
-        self.collection.insert(name, element);
-    }
-    pub fn get(&self, key: &String) -> Option<&ProjectileData> {
-        self.collection.get(key)
-    }
-}
+// Stand-ins for textures that were never loaded, added to the asset collections by `load_textures_system`
+pub const PLACEHOLDER_TEXTURE: HandleUntyped = HandleUntyped::weak_from_u64(Image::TYPE_UUID, 0x6b6f6e7175657201);
+pub const PLACEHOLDER_ATLAS: HandleUntyped = HandleUntyped::weak_from_u64(TextureAtlas::TYPE_UUID, 0x6b6f6e7175657202);
+const PLACEHOLDER_SIZE: u32 = 16;
 
 pub struct TextureServer {
-    collection: std::collections::HashMap<String, HandleUntyped>
+    collection: std::collections::HashMap<String, HandleUntyped>,
+    texture_data: std::collections::HashMap<String, TextureData>,  // Grid and animation data of atlases
+    missing: Mutex<BTreeSet<String>>,  // Keys that were asked for but never loaded
+    not_atlases: Mutex<BTreeSet<String>>  // Keys asked for as atlases that were loaded as plain textures
 }
 
 impl TextureServer {
     pub fn new() -> Self {
-        Self { collection: std::collections::HashMap::new() }
+        Self {
+            collection: std::collections::HashMap::new(),
+            texture_data: std::collections::HashMap::new(),
+            missing: Mutex::new(BTreeSet::new()),
+            not_atlases: Mutex::new(BTreeSet::new())
+        }
     }
     pub fn insert(&mut self, name: String, element: HandleUntyped) {
         self.collection.insert(name, element);
     }
+    // Unknown keys get the placeholder texture
     pub fn get(&self, key: &String) -> HandleUntyped {
         if let Some(h) = self.collection.get(key) {
             return h.clone()
         }
-        else {
-            panic!("{} is not a valid texture.", key);
+        self.report_missing(key);
+        PLACEHOLDER_TEXTURE.clone()
+    }
+    // Unknown keys, and textures loaded without grid data, get a single-frame atlas of the placeholder texture
+    pub fn get_atlas(&self, key: &String) -> Handle<TextureAtlas> {
+        match self.collection.get(key) {
+            Some(h) if self.texture_data.contains_key(key) => return h.clone().typed::<TextureAtlas>(),
+            Some(_) => {
+                if self.not_atlases.lock().unwrap().insert(key.clone()) {
+                    eprintln!("Texture {} has no grid data and cannot be animated, using a placeholder.", key);
+                }
+            },
+            None => self.report_missing(key)
+        }
+        PLACEHOLDER_ATLAS.typed::<TextureAtlas>()
+    }
+    pub fn texture_data(&self, key: &String) -> Option<&TextureData> {
+        self.texture_data.get(key)
+    }
+    // An animation of the whole atlas or one of its named ranges. Atlases without TextureData show their first frame.
+    pub fn animation(&self, key: &String, range: Option<&str>) -> AnimatedSprite {
+        match self.texture_data.get(key) {
+            Some(texture_data) => AnimatedSprite::new(texture_data, range),
+            None => AnimatedSprite::new(&TextureData {
+                tile_size_x: PLACEHOLDER_SIZE as f32,
+                tile_size_y: PLACEHOLDER_SIZE as f32,
+                columns: 1,
+                rows: 1,
+                frames: None,
+                frame_duration: 1.,
+                loop_mode: LoopMode::Once,
+                ranges: BTreeMap::new()
+            }, None)
+        }
+    }
+    pub fn contains(&self, key: &String) -> bool {
+        self.collection.contains_key(key)
+    }
+    // Logs each missing key the first time it is seen
+    pub fn report_missing(&self, key: &String) {
+        if self.missing.lock().unwrap().insert(key.clone()) {
+            eprintln!("Texture {} is missing, using a placeholder.", key);
         }
     }
+    pub fn missing(&self) -> Vec<String> {
+        self.missing.lock().unwrap().iter().cloned().collect()
+    }
     pub fn keys(&self) -> std::collections::hash_map::Keys<'_, std::string::String, bevy::prelude::HandleUntyped> {
         self.collection.keys()
     }
@@ -126,20 +99,41 @@ impl TextureServer {
 
 impl Plugin for AssetLoaderPlugin {
     fn build(&self, app: &mut App) {
+        // Apps may insert their own AssetRoot or DataPacks before adding the plugin
+        let asset_root = match app.world.get_resource::<AssetRoot>() {
+            Some(asset_root) => asset_root.clone(),
+            None => AssetRoot::from_settings(app.world.get_resource::<AssetServerSettings>())
+        };
+        let packs = match app.world.get_resource::<DataPacks>() {
+            Some(packs) => packs.clone(),
+            None => DataPacks::discover(asset_root.path())
+        };
+        for pack in packs.0.iter() {
+            println!("Loading unit data pack '{}' from {}", pack.name, pack.root.display());
+        }
+        let (database, errors) = UnitDatabase::load_packs_partial(&packs.0);
+        for error in errors.iter() {
+            eprintln!("    ...Loading failed. {}", error);
+        }
         app
-            .insert_resource( AssemblyRegistry::new() )
-            .insert_resource( SubunitRegistry::new() )
-            .insert_resource( PlatformRegistry::new() )
-            .insert_resource( ProjectileRegistry::new() )
-            .insert_resource( UnitDataCollection::new() )
+            .insert_resource( database.assemblies )
+            .insert_resource( database.subunits )
+            .insert_resource( database.platforms )
+            .insert_resource( database.projectiles )
+            .insert_resource( database.units )
+            .insert_resource( DataWatcher::new(&packs.0) )
+            .insert_resource( packs )
+            .insert_resource( asset_root )
             .insert_resource( TextureServer::new() )
+            .add_event::<UnitDataReloadedEvent>()
             .add_startup_system(load_textures_system)
             .add_startup_system(load_fonts_system)
-            .add_startup_system(load_subunits_system.after(load_textures_system))
-            .add_startup_system(load_projectiles_system.after(load_subunits_system))
-            .add_startup_system(load_assemblies_system.after(load_projectiles_system))
-            .add_startup_system(load_platforms_system.after(load_assemblies_system))
-            .add_startup_system(create_unit_data_system.after(load_platforms_system));
+            .add_system_set(SystemSet::new()  // Hot reload
+                .with_run_criteria(FixedTimestep::step(DATA_POLL_PERIOD))
+                .with_system(reload_unit_data_system)
+            )
+            .add_system(apply_reloaded_unit_data_system)
+            .add_system(check_sprite_textures_system);
             // .add_startup_system(load_platforms_system);
             // .add_system_set(SystemSet::new() // Input 
             //     .with_run_criteria(FixedTimestep::step(1. / 60.))  // VSYNC
@@ -154,10 +148,35 @@ pub struct Fonts {
     pub serif_ui: Handle<Font>
 }
 
-const ASSEMBLY_DIR: &str = "assets/data/assemblies";
-const SUBUNIT_DIR: &str = "assets/data/subunits";
-const PLATFORM_DIR: &str = "assets/data/platforms";
-const PROJECTILE_DIR: &str = "assets/data/projectiles";
+pub const ASSET_DIR: &str = "assets";
+
+// The directory the AssetServer loads from. Files found on disk are turned into asset paths relative to it, so
+// the game finds its assets regardless of the working directory it was started from.
+#[derive(Clone, Debug)]
+pub struct AssetRoot(PathBuf);
+
+impl AssetRoot {
+    pub fn new(path: &Path) -> Self {
+        Self(path.to_path_buf())
+    }
+    // Resolves the asset folder the same way as Bevy's FileAssetIo
+    pub fn from_settings(settings: Option<&AssetServerSettings>) -> Self {
+        let folder = settings.map(|settings| settings.asset_folder.as_str()).unwrap_or(ASSET_DIR);
+        Self(FileAssetIo::get_base_path().join(folder))
+    }
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+    // The path to pass to the AssetServer for a file beneath the root
+    pub fn asset_path(&self, path: &Path) -> Option<String> {
+        asset_key(path, &self.0)
+    }
+}
+
+const DATA_POLL_PERIOD: f64 = 1.;  // Seconds between checks for modified unit data
+
+// Sent after the unit data has been reloaded from disk
+pub struct UnitDataReloadedEvent;
 
 fn load_fonts_system(
     mut commands: Commands,
@@ -170,172 +189,190 @@ fn load_fonts_system(
     println!("Loaded fonts.")
 }
 
+// Textures are keyed by their path relative to the root of their data pack, so a later pack can replace a texture by
+// providing a file at the same relative path.
 fn load_textures_system(
     mut texture_server: ResMut<TextureServer>,
+    mut images: ResMut<Assets<Image>>,
     mut atlas: ResMut<Assets<TextureAtlas>>,
     asset_server: Res<AssetServer>,
+    asset_root: Res<AssetRoot>,
+    packs: Res<DataPacks>,
 ) {
-    'texture: for entry in glob::glob(&("**/*.png")).expect("Fatal: Invalid pattern") {
-        match entry {
-            Ok(path) => {
-                if let Some(s) = path.to_str() {
-                    let path_s = String::from(s).replace("\"", "");
-                    let asset_path_s = path_s.replace("assets\\", "").replace("\\", "/");
+    images.set_untracked(PLACEHOLDER_TEXTURE, placeholder_image());
+    atlas.set_untracked(
+        PLACEHOLDER_ATLAS,
+        TextureAtlas::from_grid(PLACEHOLDER_TEXTURE.typed::<Image>(), Vec2::splat(PLACEHOLDER_SIZE as f32), 1, 1)
+    );
+    for pack in packs.0.iter() {
+        'texture: for entry in find_files(&pack.root, "*.png") {
+            match entry {
+                Ok(path) => {
+                    if in_nested_pack(&packs.0, pack, &path) {
+                        continue 'texture
+                    }
+                    let (key, asset_path_s) = match (asset_key(&path, &pack.root), asset_root.asset_path(&path)) {
+                        (Some(key), Some(asset_path_s)) => (key, asset_path_s),
+                        _ => {
+                            eprintln!("Invalid texture path {:?}", path);
+                            continue 'texture
+                        }
+                    };
                     println!("Loading texture from {}...", asset_path_s);
                     let texture_handle = asset_server.load(&asset_path_s);
                     // Check for JSON file describing the texture
-                    let path_s_json = path_s.replace(".png", ".json");
-                    if let Ok(json_s) = std::fs::read_to_string(&path_s_json) {
-                        println!("   Loading texture data from {}", &path_s_json);
-                        if let Ok(texture_data) = serde_json::from_str::<TextureData>(&json_s) {
-                            let texture_atlas = TextureAtlas::from_grid(
-                                texture_handle,
-                                Vec2::new(texture_data.tile_size_x, texture_data.tile_size_y),
-                                texture_data.columns,
-                                texture_data.rows
-                            );
-                            texture_server.insert(asset_path_s.clone(), atlas.add(texture_atlas).clone_untyped());
-                            continue 'texture   
-                        }
+                    let mut errors: Vec<LoadError> = Vec::new();
+                    let texture_data = read_texture_data(&pack.root, &path, &mut errors);
+                    for error in errors.iter() {
+                        eprintln!("    ...Loading failed. {}", error);
                     }
-                    texture_server.insert(asset_path_s.clone(), texture_handle.clone_untyped());
-                }
-                else {
-                    eprintln!("Invalid texture path {:?}", path);
-                }
-            },
-            Err(e) => eprintln!("{:?}", e)
+                    if let Some(texture_data) = texture_data {
+                        println!("   Loaded texture data from {}", path.with_extension("json").display());
+                        let texture_atlas = TextureAtlas::from_grid(
+                            texture_handle,
+                            Vec2::new(texture_data.tile_size_x, texture_data.tile_size_y),
+                            texture_data.columns,
+                            texture_data.rows
+                        );
+                        texture_server.insert(key.clone(), atlas.add(texture_atlas).clone_untyped());
+                        texture_server.texture_data.insert(key, texture_data);
+                        continue 'texture
+                    }
+                    texture_server.insert(key, texture_handle.clone_untyped());
+                },
+                Err(e) => eprintln!("{:?}", e)
+            }
         }
     }
 }
 
-fn load_assemblies_system(
-    mut registry: ResMut<AssemblyRegistry>,
-) {
-    let assembly_paths = fs::read_dir(ASSEMBLY_DIR).unwrap();
-    println!("Loading assemblies from {}", ASSEMBLY_DIR);
-    for path in assembly_paths {
-        if let Ok(path) = path {
-            println!("    Found assembly {}.", path.path().display());
-            if let Ok(s) = std::fs::read_to_string(path.path()) {
-                match serde_json::from_str::<AssemblyData>(s.as_str()) {
-                    Ok(assembly) => {
-                        println!("       {:?}", assembly);
-                        registry.push(assembly);
-                    },
-                    Err(e) => { eprintln!("Failed to parse {:?}, {:?}", s.as_str(), e) }
-                }
-            }
-            else {
-                println!("      Failed to load the data!");
-            }
+// A magenta and black checkerboard
+fn placeholder_image() -> Image {
+    let mut data: Vec<u8> = Vec::with_capacity((PLACEHOLDER_SIZE * PLACEHOLDER_SIZE * 4) as usize);
+    for y in 0..PLACEHOLDER_SIZE {
+        for x in 0..PLACEHOLDER_SIZE {
+            let checked = (x < PLACEHOLDER_SIZE / 2) == (y < PLACEHOLDER_SIZE / 2);
+            data.extend_from_slice(if checked { &[255, 0, 255, 255] } else { &[0, 0, 0, 255] });
         }
     }
+    Image::new(
+        Extent3d { width: PLACEHOLDER_SIZE, height: PLACEHOLDER_SIZE, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb
+    )
 }
 
-fn load_projectiles_system(
-    mut registry: ResMut<ProjectileRegistry>,
+// Reports sprites in the registries whose textures were not loaded, so they show up before the units are spawned.
+// Runs once after startup and again whenever the unit data is reloaded.
+fn check_sprite_textures_system(
+    mut ev_reloaded: EventReader<UnitDataReloadedEvent>,
+    mut checked: Local<bool>,
+    texture_server: Res<TextureServer>,
+    subunits: Res<SubunitRegistry>,
+    platforms: Res<PlatformRegistry>,
+    projectiles: Res<ProjectileRegistry>,
 ) {
-    for entry in glob::glob(&(PROJECTILE_DIR.to_owned() + "/**/*.json")).expect("Fatal: Invalid SUBUNIT_DIR") {
-        match entry {
-            Ok(path) => {
-                if let Ok(s) = std::fs::read_to_string(&path) {
-                    match serde_json::from_str::<ProjectileData>(s.as_str()) {
-                        Ok(data) => {
-                            println!("       {:?}", data.name);
-                            registry.insert(data.name.clone(), data);
-                        },
-                        Err(e) => { eprintln!("Failed to parse {:?}, {:?}", s.as_str(), e) }
-                    }
-                }
-            }  
-            Err(e) => eprintln!("{:?}", e),
-        };
+    if ev_reloaded.iter().count() == 0 && *checked {
+        return
     }
-}
-
-fn load_subunits_system(
-    mut registry: ResMut<SubunitRegistry>,
-) {
-    for entry in glob::glob(&(SUBUNIT_DIR.to_owned() + "/**/*.json")).expect("Fatal: Invalid SUBUNIT_DIR") {
-        match entry {
-            Ok(path) => {
-                if let Ok(s) = std::fs::read_to_string(&path) {
-                    match serde_json::from_str::<SubunitData>(s.as_str()) {
-                        Ok(data) => {
-                            println!("       {:?}", data.name);
-                            registry.insert(data.name.clone(), data);
-                        },
-                        Err(e) => { eprintln!("Failed to parse {:?}, {:?}", s.as_str(), e) }
-                    }
-                }  
-            },
-            Err(e) => eprintln!("{:?}", e),
+    *checked = true;
+    let sprites = subunits.values().flat_map(|subunit| subunit.sprites.iter())
+        .chain(platforms.values().flat_map(|platform| platform.sprites.iter().chain(std::iter::once(&platform.teamcolor_sprite))))
+        .chain(projectiles.values().flat_map(|projectile| projectile.sprites.iter()));
+    for sprite in sprites {
+        if !texture_server.contains(&sprite.texture) {
+            texture_server.report_missing(&sprite.texture);
         }
     }
 }
 
-fn load_platforms_system(
-    mut registry: ResMut<PlatformRegistry>,
+// Rebuilds the registries when any of the unit data files change on disk
+fn reload_unit_data_system(
+    mut watcher: ResMut<DataWatcher>,
+    mut assemblies: ResMut<AssemblyRegistry>,
+    mut subunits: ResMut<SubunitRegistry>,
+    mut platforms: ResMut<PlatformRegistry>,
+    mut projectiles: ResMut<ProjectileRegistry>,
+    mut units: ResMut<UnitDataCollection>,
+    mut ev_reloaded: EventWriter<UnitDataReloadedEvent>,
 ) {
-    for entry in glob::glob(&(PLATFORM_DIR.to_owned() + "/**/*.json")).expect("Fatal: Invalid SUBUNIT_DIR") {
-        match entry {
-            Ok(path) => {
-                if let Ok(s) = std::fs::read_to_string(&path) {
-                    match serde_json::from_str::<PlatformData>(s.as_str()) {
-                        Ok(data) => {
-                            println!("       {:?}", data.name);
-                            registry.insert(data.name.clone(), data);
-                        },
-                        Err(e) => { eprintln!("Failed to parse {:?}, {:?}", s.as_str(), e) }
-                    }
-                }  
-            },
-            Err(e) => eprintln!("{:?}", e), 
-        }
+    if !watcher.poll() {
+        return
+    }
+    println!("Unit data changed, reloading...");
+    let (database, errors) = UnitDatabase::load_packs_partial(watcher.packs());
+    for error in errors.iter() {
+        eprintln!("    ...Loading failed. {}", error);
     }
+    *assemblies = database.assemblies;
+    *subunits = database.subunits;
+    *platforms = database.platforms;
+    *projectiles = database.projectiles;
+    *units = database.units;
+    ev_reloaded.send(UnitDataReloadedEvent);
 }
 
-fn create_unit_data_system(
-    platform_registry: Res<PlatformRegistry>,
-    subunit_registry: Res<SubunitRegistry>,
-    assembly_registry: Res<AssemblyRegistry>,
-    mut unit_data: ResMut<UnitDataCollection>,
+// Applies reloaded stats to units that are already in play. Projectile stats are read when fired and need no update.
+fn apply_reloaded_unit_data_system(
+    mut commands: Commands,
+    mut ev_reloaded: EventReader<UnitDataReloadedEvent>,
+    units: Res<UnitDataCollection>,
+    subunits: Res<SubunitRegistry>,
+    mut q_units: Query<(Entity, &Unit, &mut Hp, &mut UnitStats, &Children)>,
+    mut q_shields: Query<&mut Shield>,
+    mut q_subunits: Query<(&Subunit, Option<&mut Turret>, Option<&mut Thruster>, Option<&mut ParticleEmitter>)>,
 ) {
-    println!("Loading Unit Data...");
-    'assemblies: for assembly in assembly_registry.assemblies.iter() {
-        println!("    Loading unit {}", assembly.name);
-        println!("        Assembling on platform {}", assembly.platform);
-        if let Some(platform) = platform_registry.get(&assembly.platform) {
-            let mut loadout: Vec<SubunitData> = Vec::new();
-            for (subunit_name, hardpoint) in zip(assembly.loadout.iter(), platform.hardpoints.iter()) {
-                println!("        Assembling from subunit {}", subunit_name);
-                if let Some(subunit) = subunit_registry.get(subunit_name) {
-                    // Verify that the hardpoint fits the subunit
-                    if subunit.hardpoint_size == hardpoint.hardpoint_size {
-                        loadout.push(subunit.clone());
-                    }
-                    else {
-                        eprintln!("        ...Loading failed. Invalid hardpoint '{}'", subunit_name);
-                        continue 'assemblies
-                    }
+    if ev_reloaded.iter().count() == 0 {
+        return
+    }
+    for (entity, unit, mut hp, mut stats, children) in q_units.iter_mut() {
+        if let Some(unit_data) = units.get(&unit.name) {
+            *stats = unit_data.stats.clone();
+            let max = unit_data.stats.hp;
+            if max != hp.max {
+                hp.current = (hp.current as f64 * max as f64 / hp.max.max(1) as f64).round() as u64;
+                hp.max = max;
+            }
+            // Units that had no shield when spawned do not gain one, and units that lost their generators lose it
+            match q_shields.get_mut(entity).ok() {
+                Some(_) if unit_data.stats.shield <= 0. => {
+                    commands.entity(entity).remove::<Shield>();
+                },
+                Some(mut shield) => {
+                    let max = unit_data.stats.shield;
+                    shield.current = shield.current * max / shield.max.max(1.);
+                    shield.max = max;
+                    shield.regen_rate = unit_data.stats.shield_regen;
+                    shield.regen_delay = unit_data.stats.shield_regen_delay;
+                },
+                None => ()
+            }
+        }
+        for child in children.iter() {
+            if let Ok((subunit, turret, thruster, emitter)) = q_subunits.get_mut(*child) {
+                let subunit_data = match subunits.get(&subunit.name) {
+                    Some(subunit_data) => subunit_data,
+                    None => continue
+                };
+                if let (Some(mut turret), Some(reloaded)) = (turret, Turret::from_subunit_data(subunit_data)) {
+                    turret.reconfigure(reloaded);
                 }
-                else {
-                    eprintln!("        ...Loading failed. Could not resolve subunit '{}'", subunit_name);
-                    continue 'assemblies  // Give up on loading this Unit
+                match &subunit_data.class {
+                    SubunitClassData::Thruster { forward_thrust, particle_lifetime, particle_position_variance, particle_angle_variance, particle_sprite, .. } => {
+                        if let Some(mut thruster) = thruster {
+                            thruster.unidirectional_thrust = *forward_thrust;
+                        }
+                        if let Some(mut emitter) = emitter {
+                            emitter.lifetime = *particle_lifetime;
+                            emitter.position_variance = *particle_position_variance;
+                            emitter.angle_variance = *particle_angle_variance;
+                            emitter.sprite = particle_sprite.clone();
+                        }
+                    },
+                    SubunitClassData::Turret { .. } | SubunitClassData::ForwardWeapon { .. } | SubunitClassData::ShieldGenerator { .. } => ()
                 }
             }
-            unit_data.insert(
-                assembly.name.clone(),
-                UnitData {
-                    name: assembly.name.clone(),
-                    platform: platform.clone(),
-                    loadout: loadout,
-                }
-            )
-        }
-        else {
-            eprintln!("        ...Loading failed. Could not resolve platform '{}'", assembly.platform);
         }
     }
 }
@@ -349,3 +386,17 @@ pub fn load_projectile(path: &str) -> Option<ProjectileData> {
     }  
     panic!("Could not read file {}", path);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_texture_is_not_an_atlas() {
+        let mut texture_server = TextureServer::new();
+        let key = "data/fx/plain.png".to_string();
+        texture_server.insert(key.clone(), HandleUntyped::weak_from_u64(Image::TYPE_UUID, 1));
+        assert_eq!(texture_server.get_atlas(&key), PLACEHOLDER_ATLAS.typed::<TextureAtlas>());
+        assert!(texture_server.missing().is_empty());
+    }
+}
@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::*;
+
+// Builds the front of each depot's queue, and sends the finished unit on to the rally path.
+// A unit is paid for when work on it starts, and waits at the front of the queue until the player can afford it.
+pub fn production_system(
+    unit_data_server: Res<UnitDataCollection>,
+    mut economy: ResMut<PlayerEconomy>,
+    mut q_depot: Query<(&Unit, &Body, &mut ProductionQueue), With<Depot>>,
+    mut ev_spawn: EventWriter<SpawnUnitEvent>,
+) {
+    for (unit, body, mut production) in q_depot.iter_mut() {
+        let assembly = match production.current() {
+            Some(assembly) => assembly.clone(),
+            None => continue
+        };
+        if production.timer.is_none() {
+            match unit_data_server.get(&assembly) {
+                Some(unit_data) => {
+                    if !economy.try_spend(&unit.player, unit_data.cost) {
+                        continue
+                    }
+                    println!("Player {} started building {} ({}s)", unit.player.id, assembly, unit_data.build_time);
+                    production.timer = Some(Timer::from_seconds(unit_data.build_time, false));
+                },
+                None => {
+                    eprintln!("Cannot build unknown unit '{}'", assembly);
+                    production.queue.pop_front();
+                    continue
+                }
+            }
+        }
+        let timer = production.timer.as_mut().unwrap();
+        timer.tick(Duration::from_secs_f32(UNIT_TICK));
+        if timer.finished() {
+            production.queue.pop_front();
+            production.timer = None;
+            ev_spawn.send(
+                SpawnUnitEvent::new(assembly, unit.player.clone(), body.position).with_path(production.rally.clone())
+            );
+        }
+    }
+}
@@ -0,0 +1,497 @@
+use std::{fs, fmt, collections::BTreeMap, path::{Path, PathBuf}};
+
+use crate::*;
+
+/*
+Imports Rusted Warfare unit definitions. A unit's .ini file is read section by section:
+
+- [core] gives the unit and platform names and hitpoints
+- [graphics] gives the hull and default turret images
+- [attack] gives the range shared by all turrets
+- [turret_NAME] sections become turret subunits, each fitted to a hardpoint of the same name
+- [projectile_NAME] sections become projectiles
+- [movement] becomes a single thruster subunit fitted to an engine hardpoint at the stern. Its thrust is scaled by
+  the unit's mass so that the unit keeps its Rusted Warfare acceleration.
+
+Rusted Warfare sprites face up the screen, with y pointing down. Positions are rotated so that the unit faces +x.
+*/
+
+const RW_TICKS_PER_SECOND: f32 = 60.;
+const RW_DEFAULT_PROJECTILE_SIZE: f32 = 8.;
+const RW_DEFAULT_PROJECTILE_LIFE: f32 = 300.;
+const RW_DEFAULT_MASS: f32 = 100.;  // Stands in for a missing [core] mass, which the engine's thrust is scaled by
+const RW_ENGINE_HARDPOINT: &str = "engine";
+const RW_ENGINE_PARTICLE_SPRITE: &str = "data/fx/particle01.png";
+const RW_MOD_INFO: &str = "mod-info.txt";  // Marks the root of a mod, which `ROOT:` paths are relative to
+
+#[derive(Debug)]
+pub enum ImportError {
+    Io { path: PathBuf, error: std::io::Error },
+    Syntax { path: PathBuf, line: usize, message: String },
+    MissingKey { section: String, key: String },
+    UnresolvedProjectile { turret: String, projectile: String },
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Io { path, error } =>
+                write!(f, "{}: could not be read: {}", path.display(), error),
+            ImportError::Syntax { path, line, message } =>
+                write!(f, "{}:{}: {}", path.display(), line, message),
+            ImportError::MissingKey { section, key } =>
+                write!(f, "[{}] is missing '{}'", section, key),
+            ImportError::UnresolvedProjectile { turret, projectile } =>
+                write!(f, "[turret_{}] fires unknown projectile '{}'", turret, projectile),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+// The sections of an .ini file in the order they appear. Keys keep their last value.
+pub struct Ini {
+    sections: Vec<(String, BTreeMap<String, String>)>,
+}
+
+impl Ini {
+    pub fn parse(path: &Path, s: &str) -> Result<Ini, ImportError> {
+        let mut sections: Vec<(String, BTreeMap<String, String>)> = Vec::new();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue
+            }
+            if line.starts_with('[') {
+                match line.find(']') {
+                    Some(end) => sections.push((line[1..end].trim().to_lowercase(), BTreeMap::new())),
+                    None => return Err(ImportError::Syntax { path: path.to_path_buf(), line: i + 1, message: "unclosed section header".to_string() })
+                }
+                continue
+            }
+            let split = match line.find([':', '=']) {
+                Some(split) => split,
+                None => return Err(ImportError::Syntax { path: path.to_path_buf(), line: i + 1, message: format!("expected 'key: value', found '{}'", line) })
+            };
+            match sections.last_mut() {
+                Some((_, keys)) => {
+                    keys.insert(line[..split].trim().to_string(), line[split + 1..].trim().to_string());
+                },
+                None => return Err(ImportError::Syntax { path: path.to_path_buf(), line: i + 1, message: "key outside of a section".to_string() })
+            }
+        }
+        Ok(Ini { sections })
+    }
+
+    pub fn section(&self, name: &str) -> Option<&BTreeMap<String, String>> {
+        self.sections.iter().find(|(section, _)| section == name).map(|(_, keys)| keys)
+    }
+
+    // Sections named `<prefix>_<NAME>`, as (NAME, keys)
+    pub fn sections_with_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = (&'a str, &'a BTreeMap<String, String>)> {
+        self.sections.iter().filter_map(move |(section, keys)| {
+            section.strip_prefix(prefix).and_then(|name| name.strip_prefix('_')).map(|name| (name, keys))
+        })
+    }
+
+    pub fn get(&self, section: &str, key: &str) -> Option<&String> {
+        self.section(section).and_then(|keys| keys.get(key))
+    }
+
+    pub fn get_f32(&self, section: &str, key: &str) -> Option<f32> {
+        self.section(section).and_then(|keys| parse_number(keys.get(key)?))
+    }
+}
+
+// Numbers may carry a unit suffix, as in `buildSpeed: 3s`
+fn parse_number(s: &str) -> Option<f32> {
+    s.trim().trim_end_matches(|c: char| c.is_alphabetic()).trim().parse::<f32>().ok()
+}
+
+// Everything created from one Rusted Warfare unit
+pub struct RwImport {
+    pub platform: PlatformData,
+    pub subunits: Vec<SubunitData>,
+    pub projectiles: Vec<ProjectileData>,
+    pub assembly: AssemblyData,
+    pub textures: Vec<(PathBuf, String)>,  // Images to copy into the data pack, with the texture key they are referenced by
+    pub warnings: Vec<String>,  // Keys that could not be carried over
+}
+
+pub fn import_rusted_warfare_unit(path: &Path) -> Result<RwImport, ImportError> {
+    let s = fs::read_to_string(path).map_err(|error| ImportError::Io { path: path.to_path_buf(), error })?;
+    let ini = Ini::parse(path, &s)?;
+    let ini_dir = path.parent().unwrap_or(Path::new("."));
+    let mod_root = find_mod_root(ini_dir);
+    let images = ImageDirs { ini_dir, mod_root: mod_root.as_deref() };
+    let unit_name = ini.get("core", "name")
+        .ok_or(ImportError::MissingKey { section: "core".to_string(), key: "name".to_string() })?
+        .clone();
+    let prefix = unit_name.to_lowercase();
+    let mut textures: Vec<(PathBuf, String)> = Vec::new();
+    let mut warnings: Vec<String> = Vec::new();
+
+    if ini.get("core", "copyFrom").is_some() {
+        warnings.push("[core] copyFrom is not followed, only keys in this file were imported".to_string());
+    }
+    if ini.get_f32("core", "maxShield").is_some() {
+        warnings.push("[core] maxShield was ignored, fit a shield generator to the assembly instead".to_string());
+    }
+    // Prices in other resources are written as `credits=500, gold=10`
+    let cost = match ini.get("core", "price") {
+        Some(price) => parse_number(price).map(|price| price as u32).unwrap_or_else(|| {
+            warnings.push(format!("[core] price '{}' is not a plain number and was ignored", price));
+            0
+        }),
+        None => 0
+    };
+    // Without the `s` suffix build speed is a fraction of the unit built per tick, which has no equivalent
+    let build_time = match ini.get("core", "buildSpeed") {
+        Some(speed) if speed.trim().ends_with('s') => parse_number(speed).unwrap_or(0.),
+        Some(speed) => {
+            warnings.push(format!("[core] buildSpeed '{}' is not given in seconds and was ignored", speed));
+            0.
+        },
+        None => 0.
+    };
+
+    // Projectiles
+    let mut projectiles: Vec<ProjectileData> = Vec::new();
+    for (name, keys) in ini.sections_with_prefix("projectile") {
+        let projectile_name = format!("{}_{}", prefix, name);
+        let speed = keys.get("speed").and_then(|s| parse_number(s)).unwrap_or(1.);
+        let life = keys.get("life").and_then(|s| parse_number(s)).unwrap_or(RW_DEFAULT_PROJECTILE_LIFE);
+        let damage = keys.get("directDamage").or(keys.get("areaDamage")).and_then(|s| parse_number(s)).unwrap_or(0.);
+        let mut size = vec![RW_DEFAULT_PROJECTILE_SIZE, RW_DEFAULT_PROJECTILE_SIZE];
+        let mut sprites: Vec<SpriteData> = Vec::new();
+        if let Some(image) = keys.get("image") {
+            if let Some(sprite) = import_image(&images, image, &format!("{}/{}", PROJECTILE_DIR, projectile_name), 0., &mut textures, &mut warnings) {
+                size = sprite.size.clone();
+                sprites.push(sprite);
+            }
+        }
+        projectiles.push(ProjectileData {
+            name: projectile_name,
+            class: ProjectileClassData::Ballistic { damage_per_max_hp: 0. },
+            subclass: "shell".to_string(),
+            velocity: speed,
+            damage,
+            range: speed * life,
+            interceptable: false,
+            size,
+            sprites
+        });
+    }
+
+    // Hull
+    let platform_name = format!("{}_platform", prefix);
+    let platform_dir = format!("{}/{}", PLATFORM_DIR, platform_name);
+    let hull = match ini.get("graphics", "image") {
+        Some(image) => import_image(&images, image, &platform_dir, 1., &mut textures, &mut warnings),
+        None => {
+            warnings.push("[graphics] has no image, the hull will be invisible".to_string());
+            None
+        }
+    };
+    let radius = ini.get_f32("core", "radius").unwrap_or(20.);
+    let hull_size = hull.as_ref().map(|sprite| sprite.size.clone()).unwrap_or_else(|| vec![radius * 2., radius * 2.]);
+    let fire_range = ini.get_f32("attack", "maxAttackRange").unwrap_or(0.);
+    let mass = ini.get_f32("core", "mass").unwrap_or_else(|| {
+        warnings.push(format!("[core] has no mass, {} was used", RW_DEFAULT_MASS));
+        RW_DEFAULT_MASS
+    });
+
+    // Turrets
+    let mut subunits: Vec<SubunitData> = Vec::new();
+    let mut hardpoints: Vec<HardpointData> = Vec::new();
+    let mut loadout: BTreeMap<String, Option<String>> = BTreeMap::new();
+    for (name, keys) in ini.sections_with_prefix("turret") {
+        let get = |key: &str| keys.get(key).and_then(|s| parse_number(s));
+        let subunit_name = format!("{}_turret_{}", prefix, name);
+        let projectile = match keys.get("projectile") {
+            Some(projectile) => format!("{}_{}", prefix, projectile.to_lowercase()),
+            None => {
+                warnings.push(format!("[turret_{}] has no projectile and was skipped", name));
+                continue
+            }
+        };
+        if !projectiles.iter().any(|data| data.name == projectile) {
+            return Err(ImportError::UnresolvedProjectile { turret: name.to_string(), projectile })
+        }
+        let barrel = get("barrelY").or(get("size")).unwrap_or(0.);
+        let delay = get("delay").or(ini.get_f32("attack", "shootDelay")).unwrap_or(RW_TICKS_PER_SECOND);
+        let image = keys.get("image").or(ini.get("graphics", "image_turret"));
+        let mut sprites: Vec<SpriteData> = Vec::new();
+        let mut size = vec![barrel * 2., barrel * 2.];
+        if let Some(image) = image {
+            if let Some(sprite) = import_image(&images, image, &format!("{}/{}", SUBUNIT_DIR, subunit_name), 0., &mut textures, &mut warnings) {
+                size = sprite.size.clone();
+                sprites.push(sprite);
+            }
+        }
+        subunits.push(SubunitData {
+            name: subunit_name.clone(),
+            class: SubunitClassData::Turret {
+                reload_time: (delay * 1000. / RW_TICKS_PER_SECOND) as u64,
+                acceleration: get("turnSpeed").unwrap_or(1.),
+                fire_range,
+                angle_on_target: 5.0,
+                projectile,
+                firing_pattern: "alternating".to_string(),
+                sources: vec![rw_to_local(get("barrelX").unwrap_or(0.), -barrel)]
+            },
+            subclass: "Ballistic".to_string(),
+            hardpoint_type: HardpointType::Weapon,
+            hardpoint_size: 1,
+            mass: 0.,
+            size,
+            sprites
+        });
+        let position = rw_to_local(get("x").unwrap_or(0.), get("y").unwrap_or(0.));
+        hardpoints.push(HardpointData {
+            id: name.to_string(),
+            hardpoint_type: HardpointType::Weapon,
+            hardpoint_size: 1,
+            z_order: 3.,
+            position: vec![position[0], position[1], 0.]
+        });
+        loadout.insert(name.to_string(), Some(subunit_name));
+    }
+
+    // Engine
+    if ini.section("movement").is_some() {
+        let engine_name = format!("{}_engine", prefix);
+        subunits.push(SubunitData {
+            name: engine_name.clone(),
+            class: SubunitClassData::Thruster {
+                forward_thrust: ini.get_f32("movement", "moveAccelerationSpeed").unwrap_or(0.) * mass,
+                particle_lifetime: 1000,
+                particle_position_variance: 0.1,
+                particle_angle_variance: 0.1,
+                particle_velocity_variance: 0.1,
+                particle_color: vec![1.0, 1.0, 1.0],
+                particle_sprite: RW_ENGINE_PARTICLE_SPRITE.to_string()
+            },
+            subclass: "ThrusterThruster".to_string(),
+            hardpoint_type: HardpointType::Engine,
+            hardpoint_size: 1,
+            mass: 0.,
+            size: vec![0., 0.],
+            sprites: Vec::new()
+        });
+        hardpoints.push(HardpointData {
+            id: RW_ENGINE_HARDPOINT.to_string(),
+            hardpoint_type: HardpointType::Engine,
+            hardpoint_size: 1,
+            z_order: 0.,
+            position: vec![-hull_size[0] / 2., 0., 0.]
+        });
+        loadout.insert(RW_ENGINE_HARDPOINT.to_string(), Some(engine_name));
+    }
+
+    let teamcolor_sprite = hull.unwrap_or(SpriteData { texture: String::new(), size: hull_size.clone(), z_order: 1. });
+    let platform = PlatformData {
+        name: platform_name.clone(),
+        class: PlatformClassData::Capital {
+            range_radius: fire_range,
+            forward_burn_threshold: 10.0,
+            lateral_drag: 0.97,
+            radial_drag: 0.95
+        },
+        subclass: ini.get("movement", "movementType").cloned().unwrap_or_else(|| "NONE".to_string()).to_lowercase(),
+        hp: ini.get_f32("core", "maxHp").unwrap_or(1.) as u64,
+        mass,
+        size: hull_size,
+        sight_radius: ini.get_f32("core", "fogOfWarSightRange").unwrap_or(fire_range),
+        teamcolor_sprite,  // Rusted Warfare tints the hull itself
+        sprites: Vec::new(),
+        hardpoints,
+        armor: ArmorClass::Capital,  // Imported units fly as capitals
+        death: DeathData::default()
+    };
+    Ok(RwImport {
+        platform,
+        subunits,
+        projectiles,
+        assembly: AssemblyData { name: unit_name, platform: platform_name, loadout, build_time, cost },
+        textures,
+        warnings
+    })
+}
+
+// Rusted Warfare offsets are (right, down) with the unit facing up; ours are (forward, left)
+fn rw_to_local(x: f32, y: f32) -> Vec<f32> {
+    vec![-y, -x]
+}
+
+// The nearest directory at or above `dir` holding a mod-info.txt
+fn find_mod_root(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors().find(|ancestor| ancestor.join(RW_MOD_INFO).is_file()).map(Path::to_path_buf)
+}
+
+// Where image paths are resolved from. Plain paths are relative to the .ini, `ROOT:` paths to the mod root.
+struct ImageDirs<'a> {
+    ini_dir: &'a Path,
+    mod_root: Option<&'a Path>,
+}
+
+// Resolves an image key and queues it for copying into `dir`
+fn import_image(
+    images: &ImageDirs,
+    image: &str,
+    dir: &str,
+    z_order: f32,
+    textures: &mut Vec<(PathBuf, String)>,
+    warnings: &mut Vec<String>
+) -> Option<SpriteData> {
+    if image.eq_ignore_ascii_case("NONE") {
+        return None
+    }
+    let source = match image.strip_prefix("ROOT:") {
+        Some(rooted) => {
+            let rooted = rooted.trim_start_matches(['/', '\\']);
+            match images.mod_root {
+                Some(mod_root) => mod_root.join(rooted),
+                None => {
+                    warnings.push(format!("image '{}' is relative to the mod root, but no {} was found above the unit", image, RW_MOD_INFO));
+                    images.ini_dir.join(rooted)
+                }
+            }
+        },
+        None => images.ini_dir.join(image)
+    };
+    let size = match png_size(&source) {
+        Some(size) => size,
+        None => {
+            warnings.push(format!("image '{}' could not be read as a PNG", image));
+            return None
+        }
+    };
+    let file_name = source.file_name()?.to_string_lossy().to_string();
+    let key = format!("{}/{}", dir, file_name);
+    textures.push((source, key.clone()));
+    Some(SpriteData { texture: key, size: vec![size.0 as f32, size.1 as f32], z_order })
+}
+
+// Reads the dimensions from a PNG's header
+fn png_size(path: &Path) -> Option<(u32, u32)> {
+    let bytes = fs::read(path).ok()?;
+    if bytes.len() < 24 || &bytes[..8] != b"\x89PNG\r\n\x1a\n" || &bytes[12..16] != b"IHDR" {
+        return None
+    }
+    let width = u32::from_be_bytes(bytes[16..20].try_into().ok()?);
+    let height = u32::from_be_bytes(bytes[20..24].try_into().ok()?);
+    Some((width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_png_header(path: &Path, width: u32, height: u32) {
+        let mut bytes = b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR".to_vec();
+        bytes.extend_from_slice(&width.to_be_bytes());
+        bytes.extend_from_slice(&height.to_be_bytes());
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, bytes).unwrap();
+    }
+
+    // A unit in its own directory, removed again once imported
+    fn import_ini(name: &str, ini: &str) -> RwImport {
+        let dir = std::env::temp_dir().join(format!("konquer-rw-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{}.ini", name));
+        fs::write(&path, ini).unwrap();
+        let import = import_rusted_warfare_unit(&path);
+        fs::remove_dir_all(&dir).unwrap();
+        import.unwrap()
+    }
+
+    #[test]
+    fn units_are_converted() {
+        let import = import_ini("gunboat", "
+[core]
+name: Gunboat
+maxHp: 400
+mass: 200
+price: 250
+buildSpeed: 12s
+
+[graphics]
+image: NONE
+
+[attack]
+maxAttackRange: 180
+shootDelay: 90
+
+[turret_1]
+x: 4
+y: -10
+barrelY: 6
+projectile: 1
+delay: 30
+
+[projectile_1]
+speed: 5
+life: 40
+directDamage: 25
+
+[movement]
+movementType: WATER
+moveAccelerationSpeed: 0.05
+");
+        assert_eq!((import.assembly.name.as_str(), import.assembly.cost, import.assembly.build_time), ("Gunboat", 250, 12.));
+        assert_eq!((import.platform.hp, import.platform.mass), (400, 200.));
+
+        let projectile = &import.projectiles[0];
+        assert_eq!(projectile.name, "gunboat_1");
+        assert_eq!((projectile.velocity, projectile.damage, projectile.range), (5., 25., 200.));
+
+        let turret = import.subunits.iter().find(|subunit| subunit.name == "gunboat_turret_1").unwrap();
+        match &turret.class {
+            SubunitClassData::Turret { reload_time, fire_range, projectile, sources, .. } => {
+                assert_eq!((*reload_time, *fire_range, projectile.as_str()), (500, 180., "gunboat_1"));
+                assert_eq!(sources, &vec![vec![6., 0.]]);  // The barrel points forward
+            },
+            _ => panic!("turret_1 was not imported as a turret")
+        }
+        let hardpoint = import.platform.hardpoints.iter().find(|hardpoint| hardpoint.id == "1").unwrap();
+        assert_eq!(hardpoint.position, vec![10., -4., 0.]);
+        assert_eq!(import.assembly.loadout["1"].as_deref(), Some("gunboat_turret_1"));
+
+        let engine = import.subunits.iter().find(|subunit| subunit.name == "gunboat_engine").unwrap();
+        match &engine.class {
+            SubunitClassData::Thruster { forward_thrust, .. } => assert_eq!(*forward_thrust, 0.05 * 200.),
+            _ => panic!("movement was not imported as a thruster")
+        }
+        assert_eq!(import.assembly.loadout[RW_ENGINE_HARDPOINT].as_deref(), Some("gunboat_engine"));
+    }
+
+    #[test]
+    fn missing_mass_falls_back_to_default() {
+        let import = import_ini("drifter", "[core]\nname: Drifter\n[movement]\nmoveAccelerationSpeed: 0.1\n");
+        assert_eq!(import.platform.mass, RW_DEFAULT_MASS);
+        match &import.subunits[0].class {
+            SubunitClassData::Thruster { forward_thrust, .. } => assert!(*forward_thrust > 0.),
+            _ => panic!("movement was not imported as a thruster")
+        }
+        assert!(import.warnings.iter().any(|warning| warning.contains("mass")), "{:?}", import.warnings);
+    }
+
+    #[test]
+    fn root_images_resolve_against_mod_root() {
+        let mod_root = std::env::temp_dir().join(format!("konquer-rw-mod-{}", std::process::id()));
+        let unit_dir = mod_root.join("units").join("tank");
+        fs::create_dir_all(&unit_dir).unwrap();
+        fs::write(mod_root.join(RW_MOD_INFO), "[mod]\ntitle: Test\n").unwrap();
+        write_png_header(&mod_root.join("shared").join("hull.png"), 40, 30);
+        let ini = unit_dir.join("tank.ini");
+        fs::write(&ini, "[core]\nname: Tank\n[graphics]\nimage: ROOT:/shared/hull.png\n").unwrap();
+
+        let import = import_rusted_warfare_unit(&ini);
+        fs::remove_dir_all(&mod_root).unwrap();
+        let import = import.unwrap();
+        assert!(import.textures.iter().any(|(source, _)| *source == mod_root.join("shared").join("hull.png")), "{:?}", import.textures);
+        assert!(!import.warnings.iter().any(|warning| warning.contains("hull.png")), "{:?}", import.warnings);
+    }
+}
@@ -1,5 +1,3 @@
-use std::iter::zip;
-
 use bevy::{prelude::*};
 use bevy_prototype_lyon::prelude::*;
 
@@ -9,11 +7,17 @@ pub struct SpawnUnitEvent {
     unit_type: String,
     player: Player,
     position: Vec3,
+    path: UnitPathNodes,
 }
 
 impl SpawnUnitEvent {
     pub fn new(unit_type: String, player: Player, position: Vec3) -> SpawnUnitEvent {
-        SpawnUnitEvent { unit_type: unit_type, player: player, position: position}
+        SpawnUnitEvent { unit_type: unit_type, player: player, position: position, path: UnitPathNodes::new() }
+    }
+    // The unit sets off along `path` as soon as it is spawned
+    pub fn with_path(mut self, path: UnitPathNodes) -> SpawnUnitEvent {
+        self.path = path;
+        self
     }
 }
 
@@ -40,111 +44,114 @@ pub fn spawn_units_system(
 
             let mut ec = commands.spawn();
             ec.insert(Unit::new(ev.unit_type.clone(), ev.player.clone()));
-            let unit_hitpoints = unit_data.platform.hp;  // TODO compute from subunit buffs
+            let unit_hitpoints = unit_data.stats.hp;
             let unit_size = Vec2::new(unit_data.platform.size[0], unit_data.platform.size[1]);
             let body = Body::new(ev.position, unit_size);
-            ec.insert( Hp { max: unit_hitpoints, current: unit_hitpoints } );
+            ec.insert( Hp::new(unit_hitpoints) );
+            ec.insert( unit_data.stats.clone() );
+            if unit_data.stats.shield > 0. {
+                ec.insert( Shield::new(unit_data.stats.shield, unit_data.stats.shield_regen, unit_data.stats.shield_regen_delay) );
+            }
             ec.insert( body );
-            match unit_data.platform.class.clone() {
-                PlatformClassData::Capital { range_radius, forward_burn_threshold, lateral_drag, radial_drag } => {
-                    ec.insert( Velocity { ..Default::default() } );
-                    // TODO error checking
-                    ec.insert( Targets::new() );
-                    if ev.player.id == USER_ID {
-                        ec.insert( Targeterable );
-                        ec.insert( Movable );
-                    }
-                    else {
-                        ec.insert( Targeteeable );
-                    }
+            ec.insert( Velocity { ..Default::default() } );
+            // TODO error checking
+            ec.insert( Targets::new() );
+            if ev.player.id == USER_ID {
+                ec.insert( Targeterable );
+                ec.insert( Movable );
+            }
+            else {
+                ec.insert( Targeteeable );
+            }
+            // Role
+            match unit_data.platform.class {
+                PlatformClassData::Capital { .. } => {
                     ec.insert( CapitalShip );
-                    ec.insert( Selectable );
-                    ec.insert( UnitPath::new() );
-                    // Unit master transform
-                    ec.insert_bundle( SpatialBundle {
-                        transform: Transform {
-                            translation: Vec3::new( ev.position.x, ev.position.y, UNIT_ZORDER ),
-                            rotation: Quat::from_rotation_z( ev.position.z ),
+                },
+                PlatformClassData::Depot { .. } => {
+                    ec.insert( Depot );
+                    ec.insert( ProductionQueue::new() );
+                }
+            }
+            ec.insert( Selectable );
+            ec.insert( UnitPath { path: ev.path.clone() } );
+            // Unit master transform
+            ec.insert_bundle( SpatialBundle {
+                transform: Transform {
+                    translation: Vec3::new( ev.position.x, ev.position.y, UNIT_ZORDER ),
+                    rotation: Quat::from_rotation_z( ev.position.z ),
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+
+            ec.with_children(|parent| {
+
+                // Add sprites
+                for sprite_data in unit_data.platform.sprites.iter() {
+                    parent.spawn_bundle(sprite_bundle_from_data(sprite_data, &texture_server, 0.))
+                        .insert(MainSprite);
+                }
+                // Teamcolor sprite
+                parent.spawn_bundle(
+                    sprite_bundle_from_data(&unit_data.platform.teamcolor_sprite, &texture_server, 0.)
+                )
+                .insert(TeamSprite { color: ev.player.teamcolor } );
+
+                if DEBUG_GRAPHICS {
+                    // Debug sprites
+                    parent.spawn_bundle(SpriteBundle {
+                        sprite: Sprite {
+                            color: Color::rgba(1., 0., 0., 0.01),
+                            custom_size: Some(unit_size * SPRITE_SCALE),
                             ..Default::default()
                         },
+                        transform: Transform { translation: Vec3::new(0., 0., UNIT_ZORDER - 1.), ..Default::default() },
                         ..Default::default()
-                    });
-
-                    ec.with_children(|parent| {
-
-                        // Add sprites
-                        for sprite_data in unit_data.platform.sprites.iter() {
-                            parent.spawn_bundle(sprite_bundle_from_data(sprite_data, &texture_server, 0.))
-                                .insert(MainSprite);
-                        }
-                        // Teamcolor sprite
-                        parent.spawn_bundle(
-                            sprite_bundle_from_data(&unit_data.platform.teamcolor_sprite, &texture_server, 0.)
-                        )
-                        .insert(TeamSprite { color: ev.player.teamcolor } );
-
-                        if DEBUG_GRAPHICS {
-                            // Debug sprites
-                            parent.spawn_bundle(SpriteBundle {
-                                sprite: Sprite {
-                                    color: Color::rgba(1., 0., 0., 0.01),
-                                    custom_size: Some(unit_size * SPRITE_SCALE),
-                                    ..Default::default()
-                                },
-                                transform: Transform { translation: Vec3::new(0., 0., UNIT_ZORDER - 1.), ..Default::default() },
-                                ..Default::default()
-                            }).insert(DebugRect);
-
-                            parent.spawn_bundle(GeometryBuilder::build_as(&shapes::RegularPolygon {
-                                sides: 30,
-                                feature: shapes::RegularPolygonFeature::Radius(body.selection_radius),
-                                ..shapes::RegularPolygon::default()
-                            },
-                            DrawMode::Outlined {
-                                fill_mode: FillMode::color(Color::rgba(0., 0., 1., 0.1)),
-                                outline_mode: StrokeMode::new(Color::rgba(0., 0., 0., 0.), 2.),
-                            },
-                            Transform { translation: Vec3::new(0., 0., UNIT_ZORDER - 2.), ..Default::default() },
-                            )).insert(DebugSelectionRadius);
-
-                            parent.spawn_bundle(GeometryBuilder::build_as(&shapes::RegularPolygon {
-                                sides: 30,
-                                feature: shapes::RegularPolygonFeature::Radius(body.collision_radius),
-                                ..shapes::RegularPolygon::default()
-                            },
-                            DrawMode::Outlined {
-                                fill_mode: FillMode::color(Color::rgba(1., 0., 0.5, 0.1)),
-                                outline_mode: StrokeMode::new(Color::rgba(0., 1., 0., 0.), 2.),
-                            },
-                            Transform { translation: Vec3::new(0., 0., UI_ABOVE_ZORDER -3.), ..Default::default() },
-                            )).insert(DebugCollisionRadius);
-
-                            parent.spawn_bundle(GeometryBuilder::build_as(&shapes::RegularPolygon {
-                                sides: 30,
-                                feature: shapes::RegularPolygonFeature::Radius(body.repulsion_radius),
-                                ..shapes::RegularPolygon::default()
-                            },
-                            DrawMode::Outlined {
-                                fill_mode: FillMode::color(Color::rgba(0., 1., 0., 0.1)),
-                                outline_mode: StrokeMode::new(Color::rgba(0., 1., 0., 0.), 2.),
-                            },
-                            Transform { translation: Vec3::new(0., 0., UI_ABOVE_ZORDER - 4.), ..Default::default() },
-                            )).insert(DebugRepulsionRadius);
-                        }
-
-                        // Add subunits
-                        for (subunit, hardpoint) in zip(
-                            unit_data.loadout.iter(), 
-                            unit_data.platform.hardpoints.iter()
-                        ) {
-                            add_subunit(parent, subunit, hardpoint, &texture_server);
-                        }
-                    });   
-                },
-                PlatformClassData::Depot { forward_thrust } => {
+                    }).insert(DebugRect);
+
+                    parent.spawn_bundle(GeometryBuilder::build_as(&shapes::RegularPolygon {
+                        sides: 30,
+                        feature: shapes::RegularPolygonFeature::Radius(body.selection_radius),
+                        ..shapes::RegularPolygon::default()
+                    },
+                    DrawMode::Outlined {
+                        fill_mode: FillMode::color(Color::rgba(0., 0., 1., 0.1)),
+                        outline_mode: StrokeMode::new(Color::rgba(0., 0., 0., 0.), 2.),
+                    },
+                    Transform { translation: Vec3::new(0., 0., UNIT_ZORDER - 2.), ..Default::default() },
+                    )).insert(DebugSelectionRadius);
+
+                    parent.spawn_bundle(GeometryBuilder::build_as(&shapes::RegularPolygon {
+                        sides: 30,
+                        feature: shapes::RegularPolygonFeature::Radius(body.collision_radius),
+                        ..shapes::RegularPolygon::default()
+                    },
+                    DrawMode::Outlined {
+                        fill_mode: FillMode::color(Color::rgba(1., 0., 0.5, 0.1)),
+                        outline_mode: StrokeMode::new(Color::rgba(0., 1., 0., 0.), 2.),
+                    },
+                    Transform { translation: Vec3::new(0., 0., UI_ABOVE_ZORDER -3.), ..Default::default() },
+                    )).insert(DebugCollisionRadius);
+
+                    parent.spawn_bundle(GeometryBuilder::build_as(&shapes::RegularPolygon {
+                        sides: 30,
+                        feature: shapes::RegularPolygonFeature::Radius(body.repulsion_radius),
+                        ..shapes::RegularPolygon::default()
+                    },
+                    DrawMode::Outlined {
+                        fill_mode: FillMode::color(Color::rgba(0., 1., 0., 0.1)),
+                        outline_mode: StrokeMode::new(Color::rgba(0., 1., 0., 0.), 2.),
+                    },
+                    Transform { translation: Vec3::new(0., 0., UI_ABOVE_ZORDER - 4.), ..Default::default() },
+                    )).insert(DebugRepulsionRadius);
+                }
 
+                // Add subunits
+                for (hardpoint, subunit) in unit_data.fitted() {
+                    add_subunit(parent, subunit, hardpoint, &texture_server);
                 }
-            }
+            });   
         }
         else {
             eprintln!("Unit type {:?} not recognized.", &ev.unit_type);
@@ -193,23 +200,11 @@ fn add_subunit(
             subunit_size
         )
     )
-    .insert(Subunit { relative_position: Vec3::new(subunit_pos.x, subunit_pos.y, 0.) } );
+    .insert(Subunit { name: subunit_data.name.clone(), relative_position: Vec3::new(subunit_pos.x, subunit_pos.y, 0.) } );
+    if let Some(turret) = Turret::from_subunit_data(subunit_data) {
+        ec.insert(turret).insert(Velocity { ..Default::default() });
+    }
     match subunit_data.class.clone() {
-        SubunitClassData::Turret { reload_time, acceleration, fire_range, angle_on_target, projectile, firing_pattern, sources } => {
-            let mut vsources: Vec<Vec2> = Vec::with_capacity(sources.len());
-            for source in sources.iter() {
-                vsources.push(Vec2::new(source[0], source[1]));
-            }
-            ec.insert(Turret::new(
-                String::from(&subunit_data.name),
-                String::from(projectile),
-                fire_range,
-                reload_time,
-                firing_pattern,
-                vsources
-            ))
-            .insert(Velocity { ..Default::default() });
-        },
         SubunitClassData::Thruster { forward_thrust, particle_lifetime, particle_position_variance, particle_angle_variance, particle_velocity_variance, particle_color, particle_sprite } => {
             ec.insert(Thruster {
                 omnidirectional_thrust: 0.001,
@@ -220,7 +215,9 @@ fn add_subunit(
                 particle_angle_variance,
                 particle_sprite
             ));
-        }
+        },
+        SubunitClassData::ShieldGenerator { .. } => (),  // Shields are pooled on the unit from its stats
+        SubunitClassData::Turret { .. } | SubunitClassData::ForwardWeapon { .. } => ()
     }
 }
 
@@ -40,10 +40,19 @@ pub fn ui_highlight_selected_system(
 const HEALTHBAR_HEIGHT: f32 = 1.;
 const HEALTHBAR_WIDTH: f32 = 20.;
 const HEALTHBAR_MARGIN: f32 = 1.;
+const SHIELDBAR_COLOR: Color = Color::rgba(0.2, 0.7, 1.0, 1.0);
+
+// Every player shares a team color for now, so overlays tell the user's units and planets from everyone else's
+fn player_overlay_color(player: &Player) -> Color {
+    match player.id {
+        USER_ID => Color::rgba(0.1, 1.0, 0.1, 1.0),
+        _ => Color::rgba(1.0, 0.0, 0.0, 1.0)
+    }
+}
 
 pub fn ui_show_hp_system(
     mut commands: Commands,
-    q_units: Query<(&Unit, &Hp, &Body), With<Hp>>,
+    q_units: Query<(&Unit, &Hp, Option<&Shield>, &Body), With<Hp>>,
     q_healthbar: Query<Entity, With<HealthBar>>,
     q_camera: Query<&OrthographicProjection, With<Camera>>,
 ) {
@@ -51,51 +60,57 @@ pub fn ui_show_hp_system(
         commands.entity(bar).despawn();
     }
     let projection = q_camera.single();
-    for (unit, hp, body) in q_units.iter() {
-        let hp_color = match unit.player.id {
-            USER_ID => Color::rgba(0.1, 1.0, 0.1, 1.0),
-            _ => Color::rgba(1.0, 0.0, 0.0, 1.0)
-        };
-        let mut rect: Vec<Vec2> = vec!(
-            Vec2::new(HEALTHBAR_WIDTH, 0.),
-            Vec2::new(HEALTHBAR_WIDTH, HEALTHBAR_HEIGHT),
-            Vec2::new(0., HEALTHBAR_HEIGHT),
-            Vec2::new(0., 0.)
-        );
-        let mut path_builder = PathBuilder::new();
+    for (unit, hp, shield, body) in q_units.iter() {
+        let hp_color = player_overlay_color(&unit.player);
         let p = body.position.truncate() - Vec2::new(HEALTHBAR_WIDTH / 2., body.size.y * 1.2 * SPRITE_SCALE);
-        path_builder.move_to(p);
-        for v in rect.iter() {
-            path_builder.line_to(p + *v);
-        }
-        let outline = path_builder.build();
-        let fill = HEALTHBAR_WIDTH * (hp.current as f32 / hp.max as f32);
-        path_builder = PathBuilder::new();
-        rect[0].x = fill;
-        rect[1].x = fill;
-        for v in rect.iter() {
-            path_builder.line_to(p + *v);
+        spawn_bar(&mut commands, p, hp.current as f32 / hp.max as f32, hp_color);
+        // Shields sit on top of the health bar
+        if let Some(shield) = shield {
+            let p = p + Vec2::new(0., HEALTHBAR_HEIGHT + HEALTHBAR_MARGIN);
+            spawn_bar(&mut commands, p, shield.current / shield.max, SHIELDBAR_COLOR);
         }
-        let healthbar = path_builder.build();
-        commands.spawn_bundle(GeometryBuilder::build_as(
-            &outline,
-            DrawMode::Outlined {
-                fill_mode: FillMode::color(Color::rgba(0.4, 0.4, 0.4, 0.5)),
-                outline_mode: StrokeMode::new(Color::rgba(0., 0., 0., 0.), 1.),
-            },
-            Transform { translation: Vec3::new(0., 0., UI_ABOVE_ZORDER + 9.), ..Default::default() },
-        )).insert( HealthBar );
-        commands.spawn_bundle(GeometryBuilder::build_as(
-            &healthbar,
-            DrawMode::Outlined {
-                fill_mode: FillMode::color(hp_color),
-                outline_mode: StrokeMode::new(Color::rgba(0., 0., 0., 0.), 1.),
-            },
-            Transform { translation: Vec3::new(0., 0., UI_ABOVE_ZORDER + 10.), ..Default::default() },
-        )).insert( HealthBar );
     }
 }
 
+fn spawn_bar(commands: &mut Commands, p: Vec2, fraction: f32, color: Color) {
+    let mut rect: Vec<Vec2> = vec!(
+        Vec2::new(HEALTHBAR_WIDTH, 0.),
+        Vec2::new(HEALTHBAR_WIDTH, HEALTHBAR_HEIGHT),
+        Vec2::new(0., HEALTHBAR_HEIGHT),
+        Vec2::new(0., 0.)
+    );
+    let mut path_builder = PathBuilder::new();
+    path_builder.move_to(p);
+    for v in rect.iter() {
+        path_builder.line_to(p + *v);
+    }
+    let outline = path_builder.build();
+    let fill = HEALTHBAR_WIDTH * if fraction.is_finite() { fraction.clamp(0., 1.) } else { 0. };  // Empty pools give NaN
+    path_builder = PathBuilder::new();
+    rect[0].x = fill;
+    rect[1].x = fill;
+    for v in rect.iter() {
+        path_builder.line_to(p + *v);
+    }
+    let bar = path_builder.build();
+    commands.spawn_bundle(GeometryBuilder::build_as(
+        &outline,
+        DrawMode::Outlined {
+            fill_mode: FillMode::color(Color::rgba(0.4, 0.4, 0.4, 0.5)),
+            outline_mode: StrokeMode::new(Color::rgba(0., 0., 0., 0.), 1.),
+        },
+        Transform { translation: Vec3::new(0., 0., UI_ABOVE_ZORDER + 9.), ..Default::default() },
+    )).insert( HealthBar );
+    commands.spawn_bundle(GeometryBuilder::build_as(
+        &bar,
+        DrawMode::Outlined {
+            fill_mode: FillMode::color(color),
+            outline_mode: StrokeMode::new(Color::rgba(0., 0., 0., 0.), 1.),
+        },
+        Transform { translation: Vec3::new(0., 0., UI_ABOVE_ZORDER + 10.), ..Default::default() },
+    )).insert( HealthBar );
+}
+
 
 pub fn ui_show_path_system(
     mut commands: Commands,
@@ -139,6 +154,8 @@ pub fn ui_planet_system(
     mut q_info_ui: Query<(&mut Visibility, &mut Transform), (With<PlanetInfoUI>, Without<EnvironmentalSatellite>)>, 
     q_planets: Query<(Entity, &EnvironmentalSatellite, &Children, &Orbit, &Transform), With<EnvironmentalSatellite>>, 
     q_primary_sat: Query<&PrimarySatellite>, 
+    q_ownership: Query<(&Owner, Option<&Capture>)>,
+    mut q_owner_text: Query<(&PlanetOwnerText, &mut Text)>,
     q_transform: Query<&Transform, Without<PlanetInfoUI>>, 
     q_camera: Query<&OrthographicProjection, With<Camera>>,
     mouseover_lel: Local<Events<MouseOverEvent>>,
@@ -174,8 +191,10 @@ pub fn ui_planet_system(
                     Transform { translation: orbit_center, ..Default::default() },
                 )).insert(PlanetOrbitUI);
 
-                // Display planet's orbital radius
+                // Display planet's orbital radius, outlined in the owner's color
+                let (owner, capture) = q_ownership.get(e_planet).unwrap();
                 if let Ok(primary_sat) = q_primary_sat.get(e_planet) {
+                    let owner_color = owner.player.as_ref().map_or(Color::rgba(0., 0., 0., 0.), player_overlay_color);
                     commands.spawn_bundle(GeometryBuilder::build_as(&shapes::RegularPolygon {
                         sides: 128,
                         feature: shapes::RegularPolygonFeature::Radius(primary_sat.gravity_radius),
@@ -183,10 +202,37 @@ pub fn ui_planet_system(
                         },
                         DrawMode::Outlined {
                             fill_mode: FillMode::color(Color::rgba(0.1, 0.1, 0.1, 0.9)),
-                            outline_mode: StrokeMode::new(Color::rgba(0., 0., 0., 0.), 1.),
+                            outline_mode: StrokeMode::new(owner_color, 4. * scale_factor),
                         },
                         Transform { translation: planet_transform.translation.truncate().extend(WORLD_ZORDER + 2.), ..Default::default() },
                     )).insert(PlanetOrbitUI);
+
+                    // Display capture progress as an arc around the zone
+                    if let Some(Capture { player: Some(player), progress }) = capture {
+                        let mut path_builder = PathBuilder::new();
+                        path_builder.move_to(Vec2::new(primary_sat.gravity_radius, 0.));
+                        path_builder.arc(Vec2::ZERO, Vec2::splat(primary_sat.gravity_radius), progress * 2. * PI, 0.);
+                        commands.spawn_bundle(GeometryBuilder::build_as(
+                            &path_builder.build(),
+                            DrawMode::Stroke(StrokeMode::new(player_overlay_color(player), 8. * scale_factor)),
+                            Transform { translation: planet_transform.translation.truncate().extend(WORLD_ZORDER + 3.), ..Default::default() },
+                        )).insert(PlanetOrbitUI);
+                    }
+                }
+
+                // Describe who holds the planet and who is taking it
+                for (owner_text, mut text) in q_owner_text.iter_mut() {
+                    if owner_text.planet == e_planet {
+                        let mut description = match &owner.player {
+                            Some(player) => format!("PLAYER {}", player.id),
+                            None => "UNCLAIMED".to_string()
+                        };
+                        description += &format!("  +{:.1}/s", satellite_income(planet));
+                        if let Some(Capture { player: Some(player), progress }) = capture {
+                            description += &format!("\nPLAYER {} CAPTURING {:.0}%", player.id, progress * 100.);
+                        }
+                        text.sections[0].value = description;
+                    }
                 }
 
 
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy::ecs::schedule::ShouldRun;
+
+use crate::*;
+
+// Any one rule being met ends the match
+pub enum VictoryRule {
+    Annihilation,  // The last player with units left wins
+    PlanetMajority,  // The first player to hold more than half of the planets wins
+    Score { time_limit: f32 },  // After `time_limit` seconds the player with the highest score wins
+}
+
+pub struct MatchState {
+    pub rules: Vec<VictoryRule>,
+    pub elapsed: f32,  // Seconds of simulation
+    pub ended: bool,
+    players: Vec<Player>,  // Every player that has fielded a unit
+    kill_credit: HashMap<u8, f32>,  // Cost of the enemy units each player has destroyed
+}
+
+impl MatchState {
+    pub fn new(rules: Vec<VictoryRule>) -> MatchState {
+        MatchState { rules, elapsed: 0., ended: false, players: Vec::new(), kill_credit: HashMap::new() }
+    }
+    pub fn credit_kill(&mut self, player: &Player, cost: f32) {
+        *self.kill_credit.entry(player.id).or_default() += cost;
+    }
+}
+
+impl Default for MatchState {
+    fn default() -> MatchState {
+        MatchState::new(vec![VictoryRule::Annihilation, VictoryRule::PlanetMajority])
+    }
+}
+
+// A winner of None is a draw
+pub struct MatchEnded {
+    pub winner: Option<Player>
+}
+
+// Chained after the fixed timestep so the simulation halts once the match is over
+pub fn match_running_criteria(In(should_run): In<ShouldRun>, match_state: Res<MatchState>) -> ShouldRun {
+    if match_state.ended { ShouldRun::No } else { should_run }
+}
+
+pub fn match_state_system(
+    mut match_state: ResMut<MatchState>,
+    economy: Res<PlayerEconomy>,
+    unit_data_server: Res<UnitDataCollection>,
+    q_units: Query<&Unit>,
+    q_planets: Query<&Owner, With<PrimarySatellite>>,
+    mut ev_ended: EventWriter<MatchEnded>,
+) {
+    match_state.elapsed += UNIT_TICK;
+    for unit in q_units.iter() {
+        if !match_state.players.contains(&unit.player) {
+            match_state.players.push(unit.player.clone());
+        }
+    }
+
+    let mut result: Option<Option<Player>> = None;
+    for rule in match_state.rules.iter() {
+        result = match rule {
+            VictoryRule::Annihilation => {
+                // Only decided once there has been someone to fight
+                let remaining: Vec<&Player> = match_state.players.iter()
+                    .filter(|player| q_units.iter().any(|unit| unit.player == **player))
+                    .collect();
+                match remaining.as_slice() {
+                    [] if match_state.players.len() > 1 => Some(None),
+                    [player] if match_state.players.len() > 1 => Some(Some((*player).clone())),
+                    _ => None
+                }
+            },
+            VictoryRule::PlanetMajority => {
+                let n_planets = q_planets.iter().count();
+                match_state.players.iter()
+                    .find(|player| q_planets.iter().filter(|owner| owner.player.as_ref() == Some(*player)).count() * 2 > n_planets)
+                    .map(|player| Some(player.clone()))
+            },
+            VictoryRule::Score { time_limit } if match_state.elapsed >= *time_limit => {
+                // A player's score is their stockpile plus what their surviving units and their kills cost
+                let mut scores: HashMap<u8, f32> = match_state.players.iter()
+                    .map(|player| (player.id, economy.credits(player) + match_state.kill_credit.get(&player.id).copied().unwrap_or(0.)))
+                    .collect();
+                for unit in q_units.iter() {
+                    if let Some(unit_data) = unit_data_server.get(&unit.name) {
+                        *scores.entry(unit.player.id).or_default() += unit_data.cost as f32;
+                    }
+                }
+                let best = scores.values().cloned().fold(f32::MIN, f32::max);
+                let leaders: Vec<&Player> = match_state.players.iter().filter(|player| scores[&player.id] == best).collect();
+                match leaders.as_slice() {
+                    [player] => Some(Some((*player).clone())),
+                    _ => Some(None)
+                }
+            },
+            VictoryRule::Score { .. } => None
+        };
+        if result.is_some() {
+            break
+        }
+    }
+
+    if let Some(winner) = result {
+        match &winner {
+            Some(player) => println!("Match ended after {:.0}s, Player {} wins", match_state.elapsed, player.id),
+            None => println!("Match ended after {:.0}s in a draw", match_state.elapsed)
+        }
+        match_state.ended = true;
+        ev_ended.send(MatchEnded { winner });
+    }
+}
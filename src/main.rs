@@ -24,6 +24,7 @@ fn main() {
 		.add_plugin(konquer::UnitPlugin)
         // .add_startup_system(startup_system)
 		.add_startup_system(test_system)
+		.add_system(test_production_system)
 		.run();
 }
 
@@ -54,4 +55,23 @@ fn test_system(
 	test_spawner.send(konquer::SpawnUnitEvent::new(
 		"Frigate1".to_string(), player2.clone(), Vec3::new(150., 150., 0.)
 	));
-}
\ No newline at end of file
+
+	test_spawner.send(konquer::SpawnUnitEvent::new(
+		"Frigate1Missile".to_string(), player2.clone(), Vec3::new(-150., 150., 0.)
+	));
+
+	test_spawner.send(konquer::SpawnUnitEvent::new(
+		"Depot1".to_string(), player1.clone(), Vec3::new(-300., -300., 0.)
+	));
+}
+
+// Keep new depots busy building frigates
+fn test_production_system(
+	mut q_depot: Query<&mut konquer::ProductionQueue, Added<konquer::ProductionQueue>>,
+) {
+	for mut production in q_depot.iter_mut() {
+		production.push("Frigate1".to_string());
+		production.push("Frigate1".to_string());
+		production.rally.push_back(Vec2::new(-600., 0.));
+	}
+}